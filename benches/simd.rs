@@ -0,0 +1,68 @@
+//! Compares the vectorized pixel paths against their scalar fallbacks.
+//! Clears and triangle span fills have no vectorized path to compare, since
+//! AVX2 versions didn't beat `slice::fill`; see `salmon_rs::simd`.
+//!
+//! Run with `cargo bench --bench simd`.
+
+use salmon_rs::blit::{self, ScaleFilter, ScaleMode};
+use salmon_rs::simd;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 1000;
+
+/// Average time of one `run`, after a short warm-up.
+fn time(mut run: impl FnMut()) -> Duration {
+    for _ in 0..ITERATIONS / 10 {
+        run();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn compare(name: &str, mut run: impl FnMut()) {
+    simd::set_enabled(false);
+    let scalar = time(&mut run);
+    simd::set_enabled(true);
+    let vectorized = time(&mut run);
+
+    println!(
+        "{name:<32} scalar {:>9.1} us   simd {:>9.1} us   speedup {:.2}x",
+        scalar.as_secs_f64() * 1e6,
+        vectorized.as_secs_f64() * 1e6,
+        scalar.as_secs_f64() / vectorized.as_secs_f64(),
+    );
+}
+
+fn bench_blit(source_size: (u32, u32), target_size: (u32, u32)) {
+    let source: Vec<u32> = (0..source_size.0 * source_size.1).collect();
+    let mut target = vec![0; (target_size.0 * target_size.1) as usize];
+    let name = format!(
+        "blit {}x{} -> {}x{}",
+        source_size.0, source_size.1, target_size.0, target_size.1
+    );
+    compare(&name, || {
+        blit::present(
+            black_box(&source),
+            source_size,
+            &mut target,
+            target_size,
+            ScaleMode::Stretch,
+            ScaleFilter::Nearest,
+        );
+        black_box(&target);
+    });
+}
+
+fn main() {
+    if !simd::is_accelerated() {
+        println!("no vectorized path on this CPU; both columns use the scalar fallback");
+    }
+
+    bench_blit((64, 64), (640, 640));
+    bench_blit((320, 180), (1920, 1080));
+    bench_blit((1920, 1080), (1920, 1080));
+}
@@ -0,0 +1,209 @@
+//! Quadratic and cubic Bézier curves, flattened into polylines by adaptive
+//! subdivision so straight stretches use few segments and tight bends many.
+
+use crate::vec2::Vec2;
+
+/// Default maximum distance, in pixels, between a curve and its polyline.
+pub const DEFAULT_TOLERANCE: f32 = 0.25;
+
+/// Subdivision stops at this depth even if the tolerance isn't met, which
+/// bounds the output to `2^MAX_DEPTH` segments per curve.
+const MAX_DEPTH: u32 = 16;
+
+/// Distance from `point` to the segment `a -> b`.
+fn distance_to_segment(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let direction = b - a;
+    let length_squared = direction.length_squared();
+    if length_squared == 0.0 {
+        return (point - a).length();
+    }
+    let t = ((point - a).dot(direction) / length_squared).clamp(0.0, 1.0);
+    (point - a.lerp(b, t)).length()
+}
+
+/// Appends the points of a polyline within `tolerance` of the quadratic curve
+/// `p0, p1, p2` to `points`, ending with `p2`. `p0` itself isn't appended, so
+/// the curves of a path can be flattened one after another.
+pub fn flatten_quadratic(p0: Vec2, p1: Vec2, p2: Vec2, tolerance: f32, points: &mut Vec<Vec2>) {
+    subdivide_quadratic([p0, p1, p2], tolerance, MAX_DEPTH, points);
+}
+
+fn subdivide_quadratic(curve: [Vec2; 3], tolerance: f32, depth: u32, points: &mut Vec<Vec2>) {
+    let [p0, p1, p2] = curve;
+    // The curve lies within half the control point's distance from the chord
+    if depth == 0 || distance_to_segment(p1, p0, p2) * 0.5 <= tolerance {
+        points.push(p2);
+        return;
+    }
+
+    // Split at t = 0.5 with de Casteljau's algorithm
+    let (left, right) = (p0.lerp(p1, 0.5), p1.lerp(p2, 0.5));
+    let middle = left.lerp(right, 0.5);
+    subdivide_quadratic([p0, left, middle], tolerance, depth - 1, points);
+    subdivide_quadratic([middle, right, p2], tolerance, depth - 1, points);
+}
+
+/// Appends the points of a polyline within `tolerance` of the cubic curve
+/// `p0, p1, p2, p3` to `points`, ending with `p3`. `p0` itself isn't
+/// appended, so the curves of a path can be flattened one after another.
+pub fn flatten_cubic(
+    p0: Vec2,
+    p1: Vec2,
+    p2: Vec2,
+    p3: Vec2,
+    tolerance: f32,
+    points: &mut Vec<Vec2>,
+) {
+    subdivide_cubic([p0, p1, p2, p3], tolerance, MAX_DEPTH, points);
+}
+
+fn subdivide_cubic(curve: [Vec2; 4], tolerance: f32, depth: u32, points: &mut Vec<Vec2>) {
+    let [p0, p1, p2, p3] = curve;
+    // The curve lies within 3/4 of the farthest control point's distance from the chord
+    let flatness = distance_to_segment(p1, p0, p3).max(distance_to_segment(p2, p0, p3)) * 0.75;
+    if depth == 0 || flatness <= tolerance {
+        points.push(p3);
+        return;
+    }
+
+    let (a, b, c) = (p0.lerp(p1, 0.5), p1.lerp(p2, 0.5), p2.lerp(p3, 0.5));
+    let (d, e) = (a.lerp(b, 0.5), b.lerp(c, 0.5));
+    let middle = d.lerp(e, 0.5);
+    subdivide_cubic([p0, a, d, middle], tolerance, depth - 1, points);
+    subdivide_cubic([middle, e, c, p3], tolerance, depth - 1, points);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quadratic(p: [Vec2; 3], t: f32) -> Vec2 {
+        let s = 1.0 - t;
+        p[0] * (s * s) + p[1] * (2.0 * s * t) + p[2] * (t * t)
+    }
+
+    fn cubic(p: [Vec2; 4], t: f32) -> Vec2 {
+        let s = 1.0 - t;
+        p[0] * (s * s * s)
+            + p[1] * (3.0 * s * s * t)
+            + p[2] * (3.0 * s * t * t)
+            + p[3] * (t * t * t)
+    }
+
+    /// Largest distance from a sampled point of `curve` to the polyline
+    /// starting at `start` through `points`.
+    fn max_deviation(start: Vec2, points: &[Vec2], curve: impl Fn(f32) -> Vec2) -> f32 {
+        let mut polyline = vec![start];
+        polyline.extend_from_slice(points);
+        (0..=1000)
+            .map(|i| {
+                let point = curve(i as f32 / 1000.0);
+                polyline
+                    .windows(2)
+                    .map(|segment| distance_to_segment(point, segment[0], segment[1]))
+                    .fold(f32::INFINITY, f32::min)
+            })
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn quadratic_stays_within_tolerance() {
+        let curve = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(50.0, 120.0),
+            Vec2::new(100.0, 0.0),
+        ];
+        for tolerance in [2.0, DEFAULT_TOLERANCE, 0.01] {
+            let mut points = Vec::new();
+            flatten_quadratic(curve[0], curve[1], curve[2], tolerance, &mut points);
+
+            assert_eq!(points.last(), Some(&curve[2]));
+            let deviation = max_deviation(curve[0], &points, |t| quadratic(curve, t));
+            assert!(deviation <= tolerance + 1e-3, "{deviation} > {tolerance}");
+        }
+    }
+
+    #[test]
+    fn cubic_stays_within_tolerance() {
+        // An S-bend and a self-intersecting loop
+        let curves = [
+            [
+                Vec2::new(0.0, 0.0),
+                Vec2::new(100.0, 0.0),
+                Vec2::new(0.0, 100.0),
+                Vec2::new(100.0, 100.0),
+            ],
+            [
+                Vec2::new(0.0, 0.0),
+                Vec2::new(150.0, 80.0),
+                Vec2::new(-50.0, 80.0),
+                Vec2::new(100.0, 0.0),
+            ],
+        ];
+        for curve in curves {
+            for tolerance in [2.0, DEFAULT_TOLERANCE, 0.01] {
+                let mut points = Vec::new();
+                flatten_cubic(
+                    curve[0],
+                    curve[1],
+                    curve[2],
+                    curve[3],
+                    tolerance,
+                    &mut points,
+                );
+
+                assert_eq!(points.last(), Some(&curve[3]));
+                let deviation = max_deviation(curve[0], &points, |t| cubic(curve, t));
+                assert!(deviation <= tolerance + 1e-3, "{deviation} > {tolerance}");
+            }
+        }
+    }
+
+    #[test]
+    fn segment_count_follows_curvature() {
+        let (p0, p3) = (Vec2::new(0.0, 0.0), Vec2::new(90.0, 30.0));
+        let mut straight = Vec::new();
+        flatten_cubic(
+            p0,
+            p0.lerp(p3, 0.25),
+            p0.lerp(p3, 0.75),
+            p3,
+            DEFAULT_TOLERANCE,
+            &mut straight,
+        );
+        assert_eq!(straight, [p3]);
+
+        let count = |tolerance| {
+            let mut points = Vec::new();
+            flatten_quadratic(p0, Vec2::new(0.0, 80.0), p3, tolerance, &mut points);
+            points.len()
+        };
+        assert!(count(0.01) > count(DEFAULT_TOLERANCE));
+        assert!(count(DEFAULT_TOLERANCE) > count(2.0));
+
+        // Appending continues an existing polyline
+        let mut path = vec![p0];
+        flatten_quadratic(p0, Vec2::new(45.0, 0.0), p3, DEFAULT_TOLERANCE, &mut path);
+        flatten_quadratic(p3, Vec2::new(45.0, 60.0), p0, DEFAULT_TOLERANCE, &mut path);
+        assert_eq!((path[0], path.last()), (p0, Some(&p0)));
+    }
+
+    #[test]
+    fn degenerate_curves_terminate() {
+        let point = Vec2::new(3.0, 4.0);
+        let mut points = Vec::new();
+        flatten_cubic(point, point, point, point, DEFAULT_TOLERANCE, &mut points);
+        assert_eq!(points, [point]);
+
+        // A tolerance that can't be met stops by the depth limit
+        points.clear();
+        flatten_quadratic(
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(2.0, 0.0),
+            0.0,
+            &mut points,
+        );
+        assert!(points.len() <= 1 << MAX_DEPTH);
+    }
+}
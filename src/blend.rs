@@ -0,0 +1,145 @@
+use crate::vec4::Vec4;
+
+/// How a source color is combined with the pixel already in the framebuffer.
+/// Colors are straight (non-premultiplied) alpha unless noted otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// Overwrite the destination
+    #[default]
+    Replace,
+    /// Source-over alpha compositing
+    Alpha,
+    /// Add the alpha-weighted source to the destination
+    Additive,
+    /// Darken the destination by the source color
+    Multiply,
+    /// Lighten the destination, the inverse of multiplying the inverses
+    Screen,
+    /// Source-over for colors whose RGB is already multiplied by alpha
+    Premultiplied,
+}
+
+impl BlendMode {
+    /// Combines `source` with `destination`.
+    pub fn blend(self, source: Vec4, destination: Vec4) -> Vec4 {
+        let alpha = source.a;
+        // Every mode except replace composites coverage the same way
+        let out_alpha = alpha + destination.a * (1.0 - alpha);
+        let with_alpha = |color: Vec4| Vec4::new(color.r, color.g, color.b, out_alpha);
+
+        match self {
+            Self::Replace => source,
+            Self::Alpha => with_alpha(destination.lerp(source, alpha)),
+            Self::Additive => with_alpha(destination + source * alpha),
+            Self::Multiply => with_alpha(destination.lerp(destination * source, alpha)),
+            Self::Screen => {
+                let screen = Vec4::splat(1.0)
+                    - (Vec4::splat(1.0) - source) * (Vec4::splat(1.0) - destination);
+                with_alpha(destination.lerp(screen, alpha))
+            }
+            Self::Premultiplied => source + destination * (1.0 - alpha),
+        }
+    }
+
+    /// Scales `source` by a partial pixel coverage, e.g. from anti-aliasing.
+    /// Returns the mode to composite the result with: coverage needs
+    /// blending, so `Replace` falls back to `Alpha`.
+    pub fn apply_coverage(self, source: Vec4, coverage: f32) -> (Self, Vec4) {
+        match self {
+            Self::Premultiplied => (self, source * coverage),
+            Self::Replace if coverage >= 1.0 => (self, source),
+            Self::Replace => (
+                Self::Alpha,
+                Vec4::new(source.r, source.g, source.b, source.a * coverage),
+            ),
+            _ => (
+                self,
+                Vec4::new(source.r, source.g, source.b, source.a * coverage),
+            ),
+        }
+    }
+
+    /// Writes `source` into a packed ARGB pixel.
+    pub(crate) fn write(self, pixel: &mut u32, source: Vec4) {
+        *pixel = match self {
+            Self::Replace => source.to_argb(),
+            _ => self.blend(source, Vec4::from_argb(*pixel)).to_argb(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Destination pixel: alpha 0.6, color (0.2, 0.4, 0.8).
+    const DESTINATION: u32 = 0x9933_66cc;
+
+    /// Blends `source` over `DESTINATION` and checks every channel is within
+    /// one step of `expected`, as the float results sit near byte boundaries.
+    fn assert_writes(mode: BlendMode, source: Vec4, expected: u32) {
+        let mut pixel = DESTINATION;
+        mode.write(&mut pixel, source);
+        let close = (0..4).all(|channel| {
+            let byte = |argb: u32| (argb >> (channel * 8)) as u8;
+            byte(pixel).abs_diff(byte(expected)) <= 1
+        });
+        assert!(
+            close,
+            "{mode:?} {source:?}: {pixel:#010x} != {expected:#010x}"
+        );
+    }
+
+    #[test]
+    fn modes_blend_straight_alpha_sources() {
+        let color = |alpha| Vec4::new(1.0, 0.6, 0.0, alpha);
+        // Expected pixels for source alpha 0, 1 and 0.4
+        let cases = [
+            (BlendMode::Replace, [0x00ff_9900, 0xffff_9900, 0x66ff_9900]),
+            (BlendMode::Alpha, [DESTINATION, 0xffff_9900, 0xc184_7a7a]),
+            (BlendMode::Additive, [DESTINATION, 0xffff_ffcc, 0xc199_a3cc]),
+            (BlendMode::Multiply, [DESTINATION, 0xff33_3d00, 0xc133_557a]),
+            (BlendMode::Screen, [DESTINATION, 0xffff_c1cc, 0xc184_8acc]),
+        ];
+        for (mode, expected) in cases {
+            for (alpha, expected) in [0.0, 1.0, 0.4].into_iter().zip(expected) {
+                assert_writes(mode, color(alpha), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn premultiplied_matches_alpha_for_premultiplied_sources() {
+        let premultiplied = |alpha| Vec4::new(alpha, 0.6 * alpha, 0.0, alpha);
+        for (alpha, expected) in [(0.0, DESTINATION), (1.0, 0xffff_9900), (0.4, 0xc184_7a7a)] {
+            assert_writes(BlendMode::Premultiplied, premultiplied(alpha), expected);
+        }
+    }
+
+    #[test]
+    fn coverage_scales_alpha_and_makes_replace_blend() {
+        let source = Vec4::new(1.0, 0.6, 0.2, 0.8);
+        let with_alpha = |alpha| Vec4::new(1.0, 0.6, 0.2, alpha);
+
+        assert_eq!(
+            BlendMode::Replace.apply_coverage(source, 1.0),
+            (BlendMode::Replace, source)
+        );
+        assert_eq!(
+            BlendMode::Replace.apply_coverage(source, 0.5),
+            (BlendMode::Alpha, with_alpha(0.4))
+        );
+        assert_eq!(
+            BlendMode::Additive.apply_coverage(source, 0.5),
+            (BlendMode::Additive, with_alpha(0.4))
+        );
+        assert_eq!(
+            BlendMode::Alpha.apply_coverage(source, 0.0),
+            (BlendMode::Alpha, with_alpha(0.0))
+        );
+        assert_eq!(
+            BlendMode::Premultiplied.apply_coverage(source, 0.5),
+            (BlendMode::Premultiplied, source * 0.5)
+        );
+    }
+}
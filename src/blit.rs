@@ -0,0 +1,299 @@
+//! Copies from the canvas framebuffer to a window-sized surface buffer.
+
+use crate::simd::{self, GatherIndices};
+
+/// Color of the bars around the canvas when it doesn't fill the window.
+pub const LETTERBOX_COLOR: u32 = 0xFF00_0000;
+
+/// How the canvas is fitted into the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScaleMode {
+    /// Fill the whole window, distorting the aspect ratio if needed
+    #[default]
+    Stretch,
+    /// Largest whole-number scale that fits, centered with letterboxing. Falls
+    /// back to `Fit` when the window is smaller than the canvas.
+    Integer,
+    /// Largest scale that keeps the aspect ratio, centered with letterboxing
+    Fit,
+}
+
+impl ScaleMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "stretch" => Some(Self::Stretch),
+            "integer" => Some(Self::Integer),
+            "fit" => Some(Self::Fit),
+            _ => None,
+        }
+    }
+
+    /// Region of a `target_size` surface the canvas is drawn into.
+    pub fn viewport(self, source_size: (u32, u32), target_size: (u32, u32)) -> Viewport {
+        let (source_width, source_height) = source_size;
+        let (target_width, target_height) = target_size;
+        if source_width == 0 || source_height == 0 {
+            return Viewport::new(0, 0, 0, 0);
+        }
+
+        let integer_scale = (target_width / source_width).min(target_height / source_height);
+        let (width, height) = match self {
+            Self::Stretch => return Viewport::new(0, 0, target_width, target_height),
+            Self::Integer if integer_scale >= 1 => {
+                (source_width * integer_scale, source_height * integer_scale)
+            }
+            Self::Integer | Self::Fit => {
+                let scale = (target_width as f64 / source_width as f64)
+                    .min(target_height as f64 / source_height as f64);
+                (
+                    ((source_width as f64 * scale).round() as u32).min(target_width),
+                    ((source_height as f64 * scale).round() as u32).min(target_height),
+                )
+            }
+        };
+        Viewport::new(
+            (target_width - width) / 2,
+            (target_height - height) / 2,
+            width,
+            height,
+        )
+    }
+}
+
+/// Filter used when the canvas is scaled to the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScaleFilter {
+    /// Crisp, blocky pixels
+    #[default]
+    Nearest,
+    /// Smooth interpolation between neighbouring canvas pixels
+    Bilinear,
+}
+
+impl ScaleFilter {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nearest" => Some(Self::Nearest),
+            "bilinear" => Some(Self::Bilinear),
+            _ => None,
+        }
+    }
+}
+
+/// Rectangle of a top-down surface, in surface pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Viewport {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+}
+
+/// Scales a bottom-up `source` image into a top-down `target` according to
+/// `mode` and `filter`, filling any letterbox bars with `LETTERBOX_COLOR`.
+///
+/// Panics if either buffer doesn't match its size.
+pub fn present(
+    source: &[u32],
+    source_size: (u32, u32),
+    target: &mut [u32],
+    target_size: (u32, u32),
+    mode: ScaleMode,
+    filter: ScaleFilter,
+) {
+    assert_eq!(source.len(), pixel_count(source_size));
+    assert_eq!(target.len(), pixel_count(target_size));
+
+    let viewport = mode.viewport(source_size, target_size);
+    fill_letterbox(target, target_size, viewport);
+    if viewport.is_empty() {
+        return;
+    }
+
+    match filter {
+        ScaleFilter::Nearest => blit_nearest(source, source_size, target, target_size, viewport),
+        ScaleFilter::Bilinear => blit_bilinear(source, source_size, target, target_size, viewport),
+    }
+}
+
+/// Fills everything outside `viewport`.
+fn fill_letterbox(target: &mut [u32], target_size: (u32, u32), viewport: Viewport) {
+    let row_len = target_size.0 as usize;
+    if viewport.is_empty() {
+        target.fill(LETTERBOX_COLOR);
+        return;
+    }
+
+    let top = viewport.y as usize * row_len;
+    let bottom = (viewport.y + viewport.height) as usize * row_len;
+    target[..top].fill(LETTERBOX_COLOR);
+    target[bottom..].fill(LETTERBOX_COLOR);
+
+    if viewport.width < target_size.0 {
+        let left = viewport.x as usize;
+        let right = (viewport.x + viewport.width) as usize;
+        for row in target[top..bottom].chunks_exact_mut(row_len) {
+            row[..left].fill(LETTERBOX_COLOR);
+            row[right..].fill(LETTERBOX_COLOR);
+        }
+    }
+}
+
+/// Nearest-neighbour scales a bottom-up `source` image into `viewport` of a
+/// top-down `target`. Surface rows that sample the same canvas row are copied
+/// from the first one instead of being gathered again.
+///
+/// Panics if either buffer doesn't match its size or `viewport` exceeds the target.
+pub fn blit_nearest(
+    source: &[u32],
+    source_size: (u32, u32),
+    target: &mut [u32],
+    target_size: (u32, u32),
+    viewport: Viewport,
+) {
+    let (source_width, source_height) = source_size;
+    check_sizes(source, source_size, target, target_size, viewport);
+    if viewport.is_empty() || source.is_empty() {
+        return;
+    }
+
+    // Resolve each surface column once per frame
+    let columns = GatherIndices::new(
+        (0..viewport.width)
+            .map(|x| (x as u64 * source_width as u64 / viewport.width as u64) as usize),
+        source_width as usize,
+    );
+
+    let row_len = target_size.0 as usize;
+    let span_len = viewport.width as usize;
+    let mut previous: Option<(u32, usize)> = None;
+    for row in 0..viewport.height {
+        // Flip Y coordinate: the surface is top-down, the canvas bottom-up
+        let flipped_y = viewport.height - 1 - row;
+        let source_y = (flipped_y as u64 * source_height as u64 / viewport.height as u64) as u32;
+        let start = (viewport.y + row) as usize * row_len + viewport.x as usize;
+
+        match previous {
+            Some((y, previous_start)) if y == source_y => {
+                target.copy_within(previous_start..previous_start + span_len, start);
+            }
+            _ => {
+                let source_start = source_y as usize * source_width as usize;
+                let source_row = &source[source_start..source_start + source_width as usize];
+                simd::gather_u32(&mut target[start..start + span_len], source_row, &columns);
+                previous = Some((source_y, start));
+            }
+        }
+    }
+}
+
+/// Fixed-point bits of the bilinear filter weights.
+const WEIGHT_BITS: u32 = 8;
+const WEIGHT_ONE: u32 = 1 << WEIGHT_BITS;
+
+/// Pair of neighbouring source texels and the weight of the second one.
+#[derive(Clone, Copy)]
+struct Tap {
+    first: usize,
+    second: usize,
+    weight: u32,
+}
+
+/// Maps each of `target_len` pixel centers onto `source_len` texel centers.
+fn bilinear_taps(source_len: u32, target_len: u32) -> Vec<Tap> {
+    let scale = source_len as f32 / target_len as f32;
+    let last = source_len as usize - 1;
+    (0..target_len)
+        .map(|i| {
+            let position = ((i as f32 + 0.5) * scale - 0.5).clamp(0.0, last as f32);
+            let first = position as usize;
+            Tap {
+                first,
+                second: (first + 1).min(last),
+                weight: ((position - first as f32) * WEIGHT_ONE as f32).round() as u32,
+            }
+        })
+        .collect()
+}
+
+/// Blends two ARGB pixels, `weight` in `[0, WEIGHT_ONE]` selecting `b`. Two
+/// channels are processed per multiply in separate 16-bit lanes.
+fn lerp_argb(a: u32, b: u32, weight: u32) -> u32 {
+    const LANES: u32 = 0x00FF_00FF;
+    let inverse = WEIGHT_ONE - weight;
+    let low = ((a & LANES) * inverse + (b & LANES) * weight) >> WEIGHT_BITS;
+    let high = (((a >> 8) & LANES) * inverse + ((b >> 8) & LANES) * weight) >> WEIGHT_BITS;
+    (low & LANES) | ((high & LANES) << 8)
+}
+
+/// Bilinearly scales a bottom-up `source` image into `viewport` of a top-down
+/// `target`, sampling at pixel centers and clamping at the canvas edges.
+///
+/// Panics if either buffer doesn't match its size or `viewport` exceeds the target.
+pub fn blit_bilinear(
+    source: &[u32],
+    source_size: (u32, u32),
+    target: &mut [u32],
+    target_size: (u32, u32),
+    viewport: Viewport,
+) {
+    let (source_width, source_height) = source_size;
+    check_sizes(source, source_size, target, target_size, viewport);
+    if viewport.is_empty() || source.is_empty() {
+        return;
+    }
+
+    let columns = bilinear_taps(source_width, viewport.width);
+    let rows = bilinear_taps(source_height, viewport.height);
+    let row_len = target_size.0 as usize;
+    let source_row = |y: usize| &source[y * source_width as usize..][..source_width as usize];
+
+    // Rows are tapped bottom-up; flip them into the top-down surface
+    for (row, tap) in rows.iter().rev().enumerate() {
+        let (lower, upper) = (source_row(tap.first), source_row(tap.second));
+        let start = (viewport.y as usize + row) * row_len + viewport.x as usize;
+        let span = &mut target[start..start + viewport.width as usize];
+
+        for (pixel, column) in span.iter_mut().zip(&columns) {
+            let bottom = lerp_argb(lower[column.first], lower[column.second], column.weight);
+            let top = lerp_argb(upper[column.first], upper[column.second], column.weight);
+            *pixel = lerp_argb(bottom, top, tap.weight);
+        }
+    }
+}
+
+/// Number of pixels in an image of `size`, in `usize` so it can't overflow.
+fn pixel_count(size: (u32, u32)) -> usize {
+    size.0 as usize * size.1 as usize
+}
+
+fn check_sizes(
+    source: &[u32],
+    source_size: (u32, u32),
+    target: &[u32],
+    target_size: (u32, u32),
+    viewport: Viewport,
+) {
+    assert_eq!(source.len(), pixel_count(source_size));
+    assert_eq!(target.len(), pixel_count(target_size));
+    assert!(
+        viewport.x + viewport.width <= target_size.0
+            && viewport.y + viewport.height <= target_size.1,
+        "viewport exceeds target"
+    );
+}
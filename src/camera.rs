@@ -0,0 +1,195 @@
+use crate::input::Input;
+use crate::mat4::Mat4;
+use crate::vec3::Vec3;
+use std::f32::consts::FRAC_PI_2;
+use std::time::Duration;
+
+/// Pitch stays this far from straight up or down so the view never flips.
+const PITCH_LIMIT: f32 = FRAC_PI_2 - 0.01;
+
+/// +1, -1 or 0 depending on which of two opposing actions is held.
+fn axis(input: &Input, positive: &str, negative: &str) -> f32 {
+    let held = |action| {
+        if input.is_action_down(action) {
+            1.0
+        } else {
+            0.0
+        }
+    };
+    held(positive) - held(negative)
+}
+
+/// A perspective camera. Orientation is yaw around +Y and pitch above the
+/// horizon, both in radians; yaw 0 and pitch 0 look down -Z.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Vertical field of view in radians
+    pub fov_y: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Camera {
+    /// Camera at `position` looking towards `target`, with a 60° field of view.
+    pub fn looking_at(position: Vec3, target: Vec3) -> Self {
+        let mut camera = Self {
+            position,
+            yaw: 0.0,
+            pitch: 0.0,
+            fov_y: 60f32.to_radians(),
+            near: 0.1,
+            far: 100.0,
+        };
+        camera.look_at(target);
+        camera
+    }
+
+    /// Turns the camera to face `target`.
+    pub fn look_at(&mut self, target: Vec3) {
+        let direction = (target - self.position).normalize();
+        self.yaw = (-direction.x).atan2(-direction.z);
+        self.pitch = direction
+            .y
+            .clamp(-1.0, 1.0)
+            .asin()
+            .clamp(-PITCH_LIMIT, PITCH_LIMIT);
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        Vec3::new(-sin_yaw * cos_pitch, sin_pitch, -cos_yaw * cos_pitch)
+    }
+
+    /// Horizontal right vector, unaffected by pitch.
+    pub fn right(&self) -> Vec3 {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        Vec3::new(cos_yaw, 0.0, -sin_yaw)
+    }
+
+    pub fn up(&self) -> Vec3 {
+        self.right().cross(self.forward())
+    }
+
+    pub fn view_matrix(&self) -> Mat4 {
+        Mat4::look_at(self.position, self.position + self.forward(), Vec3::Y)
+    }
+
+    pub fn projection_matrix(&self, aspect: f32) -> Mat4 {
+        Mat4::perspective(self.fov_y, aspect, self.near, self.far)
+    }
+
+    pub fn view_projection(&self, aspect: f32) -> Mat4 {
+        self.projection_matrix(aspect) * self.view_matrix()
+    }
+}
+
+/// Orbits the camera around a target: drag `camera_rotate` to rotate, scroll
+/// to zoom and drag `camera_pan` to move the target in the view plane.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitController {
+    pub target: Vec3,
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Radians per window pixel dragged
+    pub rotate_speed: f32,
+    /// Distance factor per scroll line
+    pub zoom_speed: f32,
+    /// Fraction of the distance moved per window pixel dragged
+    pub pan_speed: f32,
+    /// Radians per second the arrow keys rotate
+    pub key_rotate_speed: f32,
+}
+
+impl OrbitController {
+    /// Orbit around `target` starting from the camera's current position.
+    pub fn new(camera: &Camera, target: Vec3) -> Self {
+        let mut facing = *camera;
+        facing.look_at(target);
+        Self {
+            target,
+            distance: (camera.position - target).length().max(1e-3),
+            yaw: facing.yaw,
+            pitch: facing.pitch,
+            rotate_speed: 0.01,
+            zoom_speed: 0.9,
+            pan_speed: 0.002,
+            key_rotate_speed: 1.5,
+        }
+    }
+
+    /// Applies this frame's input and places `camera` on the orbit.
+    pub fn update(&mut self, input: &Input, frame_delta: Duration, camera: &mut Camera) {
+        let delta = input.cursor_delta();
+        let dt = frame_delta.as_secs_f32();
+
+        if input.is_action_down("camera_rotate") {
+            self.yaw -= delta.x * self.rotate_speed;
+            self.pitch -= delta.y * self.rotate_speed;
+        }
+        self.yaw += axis(input, "orbit_left", "orbit_right") * self.key_rotate_speed * dt;
+        self.pitch += axis(input, "orbit_up", "orbit_down") * self.key_rotate_speed * dt;
+        self.pitch = self.pitch.clamp(-PITCH_LIMIT, PITCH_LIMIT);
+
+        self.distance *= self.zoom_speed.powf(input.scroll_delta().y);
+        self.distance = self.distance.clamp(camera.near * 2.0, camera.far * 0.5);
+
+        camera.yaw = self.yaw;
+        camera.pitch = self.pitch;
+        if input.is_action_down("camera_pan") {
+            let scale = self.distance * self.pan_speed;
+            self.target += camera.up() * (delta.y * scale) - camera.right() * (delta.x * scale);
+        }
+        camera.position = self.target - camera.forward() * self.distance;
+    }
+}
+
+/// First-person movement: `move_*` actions fly relative to the view, scaled
+/// by the frame delta, and holding `camera_look` turns with the mouse.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlyController {
+    /// Units per second
+    pub speed: f32,
+    /// Speed multiplier while `move_fast` is held
+    pub fast_multiplier: f32,
+    /// Radians per window pixel moved
+    pub look_speed: f32,
+}
+
+impl Default for FlyController {
+    fn default() -> Self {
+        Self {
+            speed: 2.0,
+            fast_multiplier: 4.0,
+            look_speed: 0.003,
+        }
+    }
+}
+
+impl FlyController {
+    pub fn update(&self, input: &Input, frame_delta: Duration, camera: &mut Camera) {
+        if input.is_action_down("camera_look") {
+            let delta = input.cursor_delta();
+            camera.yaw -= delta.x * self.look_speed;
+            camera.pitch =
+                (camera.pitch - delta.y * self.look_speed).clamp(-PITCH_LIMIT, PITCH_LIMIT);
+        }
+
+        let direction = camera.forward() * axis(input, "move_forward", "move_back")
+            + camera.right() * axis(input, "move_right", "move_left")
+            + Vec3::Y * axis(input, "move_up", "move_down");
+        if direction.length_squared() == 0.0 {
+            return;
+        }
+
+        let mut speed = self.speed;
+        if input.is_action_down("move_fast") {
+            speed *= self.fast_multiplier;
+        }
+        camera.position += direction.normalize() * (speed * frame_delta.as_secs_f32());
+    }
+}
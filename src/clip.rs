@@ -0,0 +1,195 @@
+//! Sutherland–Hodgman clipping in homogeneous clip space.
+//!
+//! Clipping happens before the perspective divide, so geometry behind the eye
+//! or far outside the view is cut down to the visible part instead of being
+//! projected through `w <= 0` or producing huge screen-space triangles.
+
+use crate::shader::{Varyings, VertexOutput, Weights};
+use crate::vec4::Vec4;
+
+/// The six view-frustum planes as clip-space plane equations; a point `p` is
+/// inside a plane when `plane.dot(p) >= 0` (e.g. `-w <= x` for the left plane).
+pub const FRUSTUM_PLANES: [Vec4; 6] = [
+    Vec4::new(1.0, 0.0, 0.0, 1.0),
+    Vec4::new(-1.0, 0.0, 0.0, 1.0),
+    Vec4::new(0.0, 1.0, 0.0, 1.0),
+    Vec4::new(0.0, -1.0, 0.0, 1.0),
+    Vec4::new(0.0, 0.0, 1.0, 1.0),
+    Vec4::new(0.0, 0.0, -1.0, 1.0),
+];
+
+/// Whether `position` lies on the inside of every plane.
+pub fn is_inside(position: Vec4, planes: &[Vec4]) -> bool {
+    planes.iter().all(|plane| plane.dot(position) >= 0.0)
+}
+
+/// Linear interpolation of a clipped vertex, done in clip space where both
+/// positions and perspective-correct varyings are affine. `NoPerspective`
+/// varyings are affine in screen space instead, where the new vertex lies at
+/// `t` reweighted by the endpoints' `w`.
+fn lerp_vertex<V: Varyings>(a: &VertexOutput<V>, b: &VertexOutput<V>, t: f32) -> VertexOutput<V> {
+    let position = a.position.lerp(b.position, t);
+    let screen_t = if position.w() > 0.0 {
+        t * b.position.w() / position.w()
+    } else {
+        t
+    };
+    let weights = Weights {
+        perspective: [1.0 - t, t, 0.0],
+        screen: [1.0 - screen_t, screen_t, 0.0],
+    };
+    VertexOutput {
+        position,
+        varyings: V::interpolate([&a.varyings, &b.varyings, &b.varyings], &weights),
+    }
+}
+
+/// Clips the convex polygon in `polygon` against `planes`, in place.
+/// `scratch` is reused between planes to avoid allocating per call.
+/// Leaves `polygon` empty (or with fewer than three vertices) when nothing
+/// remains visible.
+pub fn clip_polygon<V: Varyings>(
+    polygon: &mut Vec<VertexOutput<V>>,
+    scratch: &mut Vec<VertexOutput<V>>,
+    planes: &[Vec4],
+) {
+    for plane in planes {
+        if polygon.len() < 3 {
+            return;
+        }
+
+        scratch.clear();
+        for (i, current) in polygon.iter().enumerate() {
+            let next = &polygon[(i + 1) % polygon.len()];
+            let current_distance = plane.dot(current.position);
+            let next_distance = plane.dot(next.position);
+
+            if current_distance >= 0.0 {
+                scratch.push(*current);
+            }
+            // The edge crosses the plane: emit the intersection point
+            if (current_distance >= 0.0) != (next_distance >= 0.0) {
+                let t = current_distance / (current_distance - next_distance);
+                scratch.push(lerp_vertex(current, next, t));
+            }
+        }
+
+        std::mem::swap(polygon, scratch);
+    }
+}
+
+/// Clips the segment `a -> b` against `planes`, returning the visible part.
+pub fn clip_line(a: Vec4, b: Vec4, planes: &[Vec4]) -> Option<(Vec4, Vec4)> {
+    let (mut t_start, mut t_end) = (0.0f32, 1.0f32);
+
+    for plane in planes {
+        let distance_a = plane.dot(a);
+        let distance_b = plane.dot(b);
+
+        match (distance_a >= 0.0, distance_b >= 0.0) {
+            (true, true) => {}
+            (false, false) => return None,
+            (true, false) => t_end = t_end.min(distance_a / (distance_a - distance_b)),
+            (false, true) => t_start = t_start.max(distance_a / (distance_a - distance_b)),
+        }
+
+        if t_start > t_end {
+            return None;
+        }
+    }
+
+    Some((a.lerp(b, t_start), a.lerp(b, t_end)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shader::NoPerspective;
+
+    /// One perspective-correct and one screen-space varying.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Pair {
+        perspective: f32,
+        screen: NoPerspective<f32>,
+    }
+
+    impl Varyings for Pair {
+        fn interpolate(values: [&Self; 3], weights: &Weights) -> Self {
+            Self {
+                perspective: f32::interpolate(values.map(|value| &value.perspective), weights),
+                screen: NoPerspective::interpolate(values.map(|value| &value.screen), weights),
+            }
+        }
+    }
+
+    fn vertex(position: Vec4, value: f32) -> VertexOutput<Pair> {
+        VertexOutput {
+            position,
+            varyings: Pair {
+                perspective: value,
+                screen: NoPerspective(value),
+            },
+        }
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-5, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn triangle_crossing_the_near_plane_is_cut_at_it() {
+        let near = [FRUSTUM_PLANES[4]];
+        let mut polygon = vec![
+            vertex(Vec4::new(0.0, 0.0, 0.0, 1.0), 0.0),
+            vertex(Vec4::new(2.0, 0.0, -6.0, 4.0), 1.0),
+            vertex(Vec4::new(0.0, 2.0, -6.0, 4.0), 1.0),
+        ];
+        clip_polygon(&mut polygon, &mut Vec::new(), &near);
+
+        let positions: Vec<_> = polygon.iter().map(|vertex| vertex.position).collect();
+        assert_eq!(positions.len(), 3);
+        assert_eq!(positions[0], Vec4::new(0.0, 0.0, 0.0, 1.0));
+        for (&position, expected) in positions[1..].iter().zip([
+            Vec4::new(2.0 / 3.0, 0.0, -2.0, 2.0),
+            Vec4::new(0.0, 2.0 / 3.0, -2.0, 2.0),
+        ]) {
+            assert_close((position - expected).length(), 0.0);
+        }
+
+        // The cuts lie a third of the way along the edges in clip space, but
+        // two thirds of the way in screen space, where the far corners sit at
+        // x / w = 0.5 and y / w = 0.5 against the new corners' 1/3
+        for vertex in &polygon[1..] {
+            assert_close(vertex.varyings.perspective, 1.0 / 3.0);
+            assert_close(vertex.varyings.screen.0, 2.0 / 3.0);
+        }
+    }
+
+    #[test]
+    fn triangle_outside_a_plane_is_removed() {
+        let mut polygon = vec![
+            vertex(Vec4::new(0.0, 0.0, -2.0, 1.0), 0.0),
+            vertex(Vec4::new(2.0, 0.0, -6.0, 4.0), 1.0),
+            vertex(Vec4::new(0.0, 2.0, -6.0, 4.0), 1.0),
+        ];
+        clip_polygon(&mut polygon, &mut Vec::new(), &FRUSTUM_PLANES);
+        assert!(polygon.len() < 3);
+    }
+
+    #[test]
+    fn triangle_inside_every_plane_is_unchanged() {
+        let triangle = vec![
+            vertex(Vec4::new(0.0, 0.0, 0.0, 1.0), 0.0),
+            vertex(Vec4::new(1.0, 0.0, -1.0, 2.0), 1.0),
+            vertex(Vec4::new(0.0, 1.0, 1.0, 2.0), 1.0),
+        ];
+        let mut polygon = triangle.clone();
+        clip_polygon(&mut polygon, &mut Vec::new(), &FRUSTUM_PLANES);
+
+        assert_eq!(polygon.len(), 3);
+        for (clipped, original) in polygon.iter().zip(&triangle) {
+            assert_eq!(clipped.position, original.position);
+            assert_eq!(clipped.varyings, original.varyings);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use anyhow::{Context, Result};
+use std::fmt;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Number of frames `FrameStats::default` keeps, a little under a minute at 320 FPS.
+pub const DEFAULT_CAPACITY: usize = 16384;
+
+/// Ring buffer of the most recent frame durations.
+pub struct FrameStats {
+    durations: Vec<Duration>,
+    capacity: usize,
+    /// Slot the next frame is written to once the buffer is full
+    next: usize,
+    /// Frames recorded since creation, including ones already overwritten
+    total_frames: u64,
+}
+
+/// Summary of the frames currently held by `FrameStats`. Lows are the frame
+/// rates over the slowest 1% and 0.1% of frames, which expose stutter that
+/// an average hides.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameSummary {
+    pub frames: usize,
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub low_1_percent_fps: f32,
+    pub low_0_1_percent_fps: f32,
+}
+
+impl FrameStats {
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "frame stats need room for at least one frame");
+        Self {
+            durations: Vec::with_capacity(capacity),
+            capacity,
+            next: 0,
+            total_frames: 0,
+        }
+    }
+
+    pub fn record(&mut self, duration: Duration) {
+        if self.durations.len() < self.capacity {
+            self.durations.push(duration);
+        } else {
+            self.durations[self.next] = duration;
+            self.next = (self.next + 1) % self.capacity;
+        }
+        self.total_frames += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.durations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.durations.is_empty()
+    }
+
+    pub fn total_frames(&self) -> u64 {
+        self.total_frames
+    }
+
+    /// Retained durations from oldest to newest.
+    pub fn durations(&self) -> impl Iterator<Item = Duration> + '_ {
+        let (newer, older) = self.durations.split_at(self.next);
+        older.iter().chain(newer).copied()
+    }
+
+    pub fn summary(&self) -> Option<FrameSummary> {
+        if self.durations.is_empty() {
+            return None;
+        }
+
+        let mut sorted = self.durations.clone();
+        sorted.sort_unstable();
+        let total: Duration = sorted.iter().sum();
+
+        // Nearest-rank percentile
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+        // Average frame rate over the slowest `p` percent of frames
+        let low = |p: f64| {
+            let count = ((p / 100.0 * sorted.len() as f64).ceil() as usize).max(1);
+            let slowest: Duration = sorted[sorted.len() - count..].iter().sum();
+            count as f32 / slowest.as_secs_f32()
+        };
+
+        Some(FrameSummary {
+            frames: sorted.len(),
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            mean: total / sorted.len() as u32,
+            p50: percentile(50.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+            low_1_percent_fps: low(1.0),
+            low_0_1_percent_fps: low(0.1),
+        })
+    }
+
+    /// Counts retained frames into `buckets` bins of `bucket_width` each; the
+    /// last bin also collects everything longer.
+    pub fn histogram(&self, bucket_width: Duration, buckets: usize) -> Vec<u32> {
+        let mut counts = vec![0; buckets];
+        if buckets == 0 || bucket_width.is_zero() {
+            return counts;
+        }
+        for duration in &self.durations {
+            let bucket = (duration.as_nanos() / bucket_width.as_nanos()) as usize;
+            counts[bucket.min(buckets - 1)] += 1;
+        }
+        counts
+    }
+
+    /// Writes the retained durations, oldest first, as `frame,duration_ms` rows.
+    /// Frame numbers count from the first frame ever recorded.
+    pub fn write_csv(&self, path: &Path) -> Result<()> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+
+        let first_frame = self.total_frames - self.durations.len() as u64;
+        writeln!(writer, "frame,duration_ms")?;
+        for (frame, duration) in (first_frame..).zip(self.durations()) {
+            writeln!(writer, "{},{:.4}", frame, duration.as_secs_f64() * 1000.0)?;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl fmt::Display for FrameSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "{} frames: min {:.2} ms, mean {:.2} ms, max {:.2} ms, \
+             p50 {:.2} ms, p95 {:.2} ms, p99 {:.2} ms, \
+             1% low {:.1} FPS, 0.1% low {:.1} FPS",
+            self.frames,
+            ms(self.min),
+            ms(self.mean),
+            ms(self.max),
+            ms(self.p50),
+            ms(self.p95),
+            ms(self.p99),
+            self.low_1_percent_fps,
+            self.low_0_1_percent_fps,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(value: u64) -> Duration {
+        Duration::from_millis(value)
+    }
+
+    #[test]
+    fn summary_uses_nearest_rank_percentiles() {
+        let mut stats = FrameStats::new(256);
+        // 1 to 200 ms, recorded out of order
+        for i in 0..200 {
+            stats.record(millis((i * 73) % 200 + 1));
+        }
+
+        let summary = stats.summary().unwrap();
+        assert_eq!(summary.frames, 200);
+        assert_eq!(summary.min, millis(1));
+        assert_eq!(summary.max, millis(200));
+        assert_eq!(summary.mean, Duration::from_micros(100_500));
+        assert_eq!(summary.p50, millis(100));
+        assert_eq!(summary.p95, millis(190));
+        assert_eq!(summary.p99, millis(198));
+        // Slowest 2 frames take 399 ms, the slowest 1 takes 200 ms
+        assert!((summary.low_1_percent_fps - 2.0 / 0.399).abs() < 1e-3);
+        assert!((summary.low_0_1_percent_fps - 5.0).abs() < 1e-3);
+
+        assert_eq!(stats.histogram(millis(50), 3), [49, 50, 101]);
+        assert!(FrameStats::new(4).summary().is_none());
+    }
+
+    #[test]
+    fn full_buffer_overwrites_the_oldest_frames() {
+        let mut stats = FrameStats::new(5);
+        for value in 1..=8 {
+            stats.record(millis(value));
+        }
+
+        assert_eq!(stats.len(), 5);
+        assert_eq!(stats.total_frames(), 8);
+        let retained: Vec<_> = stats.durations().collect();
+        assert_eq!(retained, [4, 5, 6, 7, 8].map(millis));
+
+        let summary = stats.summary().unwrap();
+        assert_eq!(
+            (summary.min, summary.p50, summary.max),
+            (millis(4), millis(6), millis(8))
+        );
+    }
+}
@@ -0,0 +1,705 @@
+use crate::zlib;
+use anyhow::{Context, Result, bail};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Largest width or height the decoders accept.
+pub const MAX_IMAGE_DIMENSION: u32 = 1 << 16;
+
+/// Largest pixel count the decoders accept, so a corrupt or hostile header
+/// can't make them allocate gigabytes before the data runs out.
+pub const MAX_IMAGE_PIXELS: usize = 1 << 28;
+
+/// Image file formats the canvas can be written to and read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Ppm,
+    Tga,
+}
+
+impl ImageFormat {
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(Self::Png),
+            "ppm" => Some(Self::Ppm),
+            "tga" => Some(Self::Tga),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Self::from_extension)
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Ppm => "ppm",
+            Self::Tga => "tga",
+        }
+    }
+}
+
+/// Decoded image in the canvas layout: ARGB pixels, bottom row first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageData {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u32>,
+}
+
+/// Reads a PNG, PPM or TGA file, choosing the decoder from the file extension.
+pub fn read_image(path: &Path) -> Result<ImageData> {
+    let Some(format) = ImageFormat::from_path(path) else {
+        bail!("Unsupported image extension: {}", path.display());
+    };
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    decode_image(&bytes, format).with_context(|| format!("Failed to decode {}", path.display()))
+}
+
+pub fn decode_image(bytes: &[u8], format: ImageFormat) -> Result<ImageData> {
+    match format {
+        ImageFormat::Png => decode_png(bytes),
+        ImageFormat::Ppm => decode_ppm(bytes),
+        ImageFormat::Tga => decode_tga(bytes),
+    }
+}
+
+/// Writes ARGB pixels stored bottom row first (the canvas layout) to `path`,
+/// choosing the encoder from the file extension.
+pub fn write_image(path: &Path, width: u32, height: u32, pixels: &[u32]) -> Result<()> {
+    let Some(format) = ImageFormat::from_path(path) else {
+        bail!("Unsupported image extension: {}", path.display());
+    };
+    write_image_as(path, format, width, height, pixels)
+}
+
+pub fn write_image_as(
+    path: &Path,
+    format: ImageFormat,
+    width: u32,
+    height: u32,
+    pixels: &[u32],
+) -> Result<()> {
+    if width == 0 || height == 0 {
+        bail!("Can't write an empty {}x{} image", width, height);
+    }
+    if Some(pixels.len()) != (width as usize).checked_mul(height as usize) {
+        bail!(
+            "Pixel buffer holds {} pixels, expected {}x{}",
+            pixels.len(),
+            width,
+            height
+        );
+    }
+
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+
+    match format {
+        ImageFormat::Png => encode_png(&mut writer, width, height, pixels)?,
+        ImageFormat::Ppm => encode_ppm(&mut writer, width, height, pixels)?,
+        ImageFormat::Tga => encode_tga(&mut writer, width, height, pixels)?,
+    }
+
+    writer
+        .flush()
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Number of pixels in a decoded `width x height` image, checked against
+/// `MAX_IMAGE_DIMENSION` and `MAX_IMAGE_PIXELS`.
+fn pixel_count(width: u32, height: u32) -> Result<usize> {
+    if width > MAX_IMAGE_DIMENSION || height > MAX_IMAGE_DIMENSION {
+        bail!("image dimensions {}x{} are too large", width, height);
+    }
+    match (width as usize).checked_mul(height as usize) {
+        Some(count) if count <= MAX_IMAGE_PIXELS => Ok(count),
+        _ => bail!("image dimensions {}x{} are too large", width, height),
+    }
+}
+
+/// Iterates rows top-down, as most image formats expect.
+fn rows_top_down(width: u32, pixels: &[u32]) -> impl Iterator<Item = &[u32]> {
+    pixels.chunks_exact(width as usize).rev()
+}
+
+fn encode_ppm(writer: &mut impl Write, width: u32, height: u32, pixels: &[u32]) -> Result<()> {
+    write!(writer, "P6\n{} {}\n255\n", width, height)?;
+    for row in rows_top_down(width, pixels) {
+        for &argb in row {
+            writer.write_all(&[(argb >> 16) as u8, (argb >> 8) as u8, argb as u8])?;
+        }
+    }
+    Ok(())
+}
+
+fn encode_tga(writer: &mut impl Write, width: u32, height: u32, pixels: &[u32]) -> Result<()> {
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        bail!("TGA images are limited to 65535x65535");
+    }
+
+    let mut header = [0u8; 18];
+    header[2] = 2; // Uncompressed true-color
+    header[12..14].copy_from_slice(&(width as u16).to_le_bytes());
+    header[14..16].copy_from_slice(&(height as u16).to_le_bytes());
+    header[16] = 32; // Bits per pixel
+    header[17] = 8; // Alpha bits, bottom-left origin
+    writer.write_all(&header)?;
+
+    // TGA's default origin is bottom-left, which matches the canvas layout
+    for &argb in pixels {
+        writer.write_all(&argb.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn encode_png(writer: &mut impl Write, width: u32, height: u32, pixels: &[u32]) -> Result<()> {
+    writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[8, 6, 0, 0, 0]); // 8-bit RGBA, no interlace
+    write_png_chunk(writer, b"IHDR", &header)?;
+
+    // Each scanline is prefixed with filter type 0 (none)
+    let mut scanlines = Vec::with_capacity(height as usize * (width as usize * 4 + 1));
+    for row in rows_top_down(width, pixels) {
+        scanlines.push(0);
+        for &argb in row {
+            scanlines.extend_from_slice(&[
+                (argb >> 16) as u8,
+                (argb >> 8) as u8,
+                argb as u8,
+                (argb >> 24) as u8,
+            ]);
+        }
+    }
+    write_png_chunk(writer, b"IDAT", &zlib::compress_stored(&scanlines))?;
+    write_png_chunk(writer, b"IEND", &[])?;
+    Ok(())
+}
+
+fn write_png_chunk(writer: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+
+    let mut crc = Crc32::new();
+    crc.update(kind);
+    crc.update(data);
+    writer.write_all(&crc.finish().to_be_bytes())?;
+    Ok(())
+}
+
+struct Crc32 {
+    table: [u32; 256],
+    value: u32,
+}
+
+impl Crc32 {
+    fn new() -> Self {
+        let mut table = [0u32; 256];
+        for (n, entry) in table.iter_mut().enumerate() {
+            let mut c = n as u32;
+            for _ in 0..8 {
+                c = if c & 1 != 0 {
+                    0xEDB8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+            }
+            *entry = c;
+        }
+        Self {
+            table,
+            value: 0xFFFF_FFFF,
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.value =
+                self.table[((self.value ^ byte as u32) & 0xFF) as usize] ^ (self.value >> 8);
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        self.value ^ 0xFFFF_FFFF
+    }
+}
+
+fn argb(r: u8, g: u8, b: u8, a: u8) -> u32 {
+    ((a as u32) << 24) | ((r as u32) << 16) | ((g as u32) << 8) | b as u32
+}
+
+/// Builds an `ImageData` from rows given top-down.
+fn from_top_down(width: u32, height: u32, mut pixels: Vec<u32>) -> ImageData {
+    let row_len = width as usize;
+    for y in 0..height as usize / 2 {
+        let bottom = (height as usize - 1 - y) * row_len;
+        let (top_half, bottom_half) = pixels.split_at_mut(bottom);
+        top_half[y * row_len..(y + 1) * row_len].swap_with_slice(&mut bottom_half[..row_len]);
+    }
+    ImageData {
+        width,
+        height,
+        pixels,
+    }
+}
+
+/// Binary (`P6`, `P5`) and ASCII (`P3`, `P2`) color and grayscale PNM images.
+fn decode_ppm(bytes: &[u8]) -> Result<ImageData> {
+    let mut header = PpmHeaderReader { bytes, position: 0 };
+    let magic = header.token()?;
+    let (channels, binary) = match magic {
+        b"P6" => (3, true),
+        b"P5" => (1, true),
+        b"P3" => (3, false),
+        b"P2" => (1, false),
+        _ => bail!("unsupported PPM variant"),
+    };
+    let width = header.number()?;
+    let height = header.number()?;
+    let max_value = header.number()?;
+    if max_value == 0 || max_value > u16::MAX as u32 {
+        bail!("invalid PPM maximum value {}", max_value);
+    }
+
+    let sample_count = pixel_count(width, height)? * channels as usize;
+    let samples: Vec<u32> = if binary {
+        // Exactly one whitespace byte separates the header from the raster
+        let start = header.position + 1;
+        let wide = max_value > 255;
+        let size = sample_count * if wide { 2 } else { 1 };
+        let Some(raster) = bytes.get(start..start + size) else {
+            bail!("PPM raster is truncated");
+        };
+        if wide {
+            raster
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]) as u32)
+                .collect()
+        } else {
+            raster.iter().map(|&byte| byte as u32).collect()
+        }
+    } else {
+        (0..sample_count)
+            .map(|_| header.number())
+            .collect::<Result<_>>()?
+    };
+
+    let scale = |sample: u32| (sample.min(max_value) * 255 / max_value) as u8;
+    let pixels = samples
+        .chunks_exact(channels as usize)
+        .map(|pixel| match pixel {
+            [r, g, b] => argb(scale(*r), scale(*g), scale(*b), 255),
+            _ => {
+                let value = scale(pixel[0]);
+                argb(value, value, value, 255)
+            }
+        })
+        .collect();
+    Ok(from_top_down(width, height, pixels))
+}
+
+/// Tokenizer for the whitespace-separated, `#`-commented PNM header.
+struct PpmHeaderReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> PpmHeaderReader<'a> {
+    fn token(&mut self) -> Result<&'a [u8]> {
+        let bytes = self.bytes;
+        loop {
+            match bytes.get(self.position) {
+                Some(b'#') => {
+                    while bytes.get(self.position).is_some_and(|&byte| byte != b'\n') {
+                        self.position += 1;
+                    }
+                }
+                Some(byte) if byte.is_ascii_whitespace() => self.position += 1,
+                Some(_) => break,
+                None => bail!("unexpected end of PPM data"),
+            }
+        }
+        let start = self.position;
+        while bytes
+            .get(self.position)
+            .is_some_and(|byte| !byte.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+        Ok(&bytes[start..self.position])
+    }
+
+    fn number(&mut self) -> Result<u32> {
+        let value = self.token()?;
+        std::str::from_utf8(value)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .with_context(|| format!("invalid PPM number '{}'", String::from_utf8_lossy(value)))
+    }
+}
+
+/// Uncompressed and run-length encoded true-color and grayscale TGA images.
+fn decode_tga(bytes: &[u8]) -> Result<ImageData> {
+    let Some(header) = bytes.get(..18) else {
+        bail!("TGA header is truncated");
+    };
+    let id_length = header[0] as usize;
+    let color_map_type = header[1];
+    let image_type = header[2];
+    let color_map_size =
+        u16::from_le_bytes([header[5], header[6]]) as usize * header[7].div_ceil(8) as usize;
+    let width = u16::from_le_bytes([header[12], header[13]]) as u32;
+    let height = u16::from_le_bytes([header[14], header[15]]) as u32;
+    let bits_per_pixel = header[16];
+    let top_down = header[17] & 0x20 != 0;
+
+    let run_length_encoded = match image_type {
+        2 | 3 => false,
+        10 | 11 => true,
+        _ => bail!("unsupported TGA image type {}", image_type),
+    };
+    let grayscale = image_type == 3 || image_type == 11;
+    let bytes_per_pixel = match (grayscale, bits_per_pixel) {
+        (true, 8) => 1,
+        (false, 24) => 3,
+        (false, 32) => 4,
+        _ => bail!("unsupported TGA pixel depth {}", bits_per_pixel),
+    };
+
+    let to_argb = |pixel: &[u8]| match pixel {
+        [value] => argb(*value, *value, *value, 255),
+        [b, g, r] => argb(*r, *g, *b, 255),
+        [b, g, r, a] => argb(*r, *g, *b, *a),
+        _ => unreachable!(),
+    };
+
+    let pixel_count = pixel_count(width, height)?;
+    let mut data = bytes
+        .get(
+            18 + id_length
+                + if color_map_type != 0 {
+                    color_map_size
+                } else {
+                    0
+                }..,
+        )
+        .unwrap_or_default();
+    let mut pixels = Vec::with_capacity(pixel_count);
+    let mut take = |count: usize| -> Result<&[u8]> {
+        let Some((taken, rest)) = data.split_at_checked(count) else {
+            bail!("TGA pixel data is truncated");
+        };
+        data = rest;
+        Ok(taken)
+    };
+
+    while pixels.len() < pixel_count {
+        if run_length_encoded {
+            let packet = take(1)?[0];
+            let count = (packet & 0x7F) as usize + 1;
+            if packet & 0x80 != 0 {
+                let value = to_argb(take(bytes_per_pixel)?);
+                pixels.extend(std::iter::repeat_n(value, count));
+            } else {
+                pixels.extend(
+                    take(count * bytes_per_pixel)?
+                        .chunks_exact(bytes_per_pixel)
+                        .map(to_argb),
+                );
+            }
+        } else {
+            pixels.extend(
+                take(pixel_count * bytes_per_pixel)?
+                    .chunks_exact(bytes_per_pixel)
+                    .map(to_argb),
+            );
+        }
+    }
+    pixels.truncate(pixel_count);
+
+    if top_down {
+        Ok(from_top_down(width, height, pixels))
+    } else {
+        Ok(ImageData {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+/// Non-interlaced PNGs of every color type, at any bit depth.
+fn decode_png(bytes: &[u8]) -> Result<ImageData> {
+    if !bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        bail!("missing PNG signature");
+    }
+
+    let mut header = None;
+    let mut palette: Vec<[u8; 4]> = Vec::new();
+    let mut transparency: Option<Vec<u8>> = None;
+    let mut compressed = Vec::new();
+
+    let mut position = 8;
+    while let Some(length_bytes) = bytes.get(position..position + 4) {
+        let length = u32::from_be_bytes(length_bytes.try_into()?) as usize;
+        let Some(kind) = bytes.get(position + 4..position + 8) else {
+            bail!("PNG chunk is truncated");
+        };
+        let Some(data) = bytes.get(position + 8..position + 8 + length) else {
+            bail!("PNG chunk is truncated");
+        };
+        position += 12 + length;
+
+        match kind {
+            b"IHDR" => {
+                if data.len() != 13 {
+                    bail!("invalid PNG header");
+                }
+                header = Some(PngHeader {
+                    width: u32::from_be_bytes(data[0..4].try_into()?),
+                    height: u32::from_be_bytes(data[4..8].try_into()?),
+                    bit_depth: data[8],
+                    color_type: data[9],
+                    interlaced: data[12] != 0,
+                });
+            }
+            b"PLTE" => {
+                palette = data
+                    .chunks_exact(3)
+                    .map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                    .collect();
+            }
+            b"tRNS" => transparency = Some(data.to_vec()),
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+    }
+
+    let Some(header) = header else {
+        bail!("PNG has no header");
+    };
+    if header.interlaced {
+        bail!("interlaced PNGs are not supported");
+    }
+    let channels = match header.color_type {
+        0 | 3 => 1,
+        4 => 2,
+        2 => 3,
+        6 => 4,
+        _ => bail!("invalid PNG color type {}", header.color_type),
+    };
+    if !matches!(header.bit_depth, 1 | 2 | 4 | 8 | 16) {
+        bail!("invalid PNG bit depth {}", header.bit_depth);
+    }
+    if let (3, Some(alpha)) = (header.color_type, &transparency) {
+        for (entry, &alpha) in palette.iter_mut().zip(alpha) {
+            entry[3] = alpha;
+        }
+    }
+
+    let pixel_count = pixel_count(header.width, header.height)?;
+    let bits_per_pixel = channels * header.bit_depth as usize;
+    let stride = (header.width as usize * bits_per_pixel).div_ceil(8);
+    let filter_step = bits_per_pixel.div_ceil(8);
+    let data_len = (stride + 1) * header.height as usize;
+    let mut data = zlib::decompress(&compressed, data_len)?;
+    if data.len() < data_len {
+        bail!("PNG image data is truncated");
+    }
+
+    // Undo the per-scanline filters in place
+    let mut previous = vec![0u8; stride];
+    let mut pixels = Vec::with_capacity(pixel_count);
+    for row_index in 0..header.height as usize {
+        let row_start = row_index * (stride + 1);
+        let filter = data[row_start];
+        let row = &mut data[row_start + 1..row_start + 1 + stride];
+        unfilter_png_row(filter, row, &previous, filter_step)?;
+
+        let sample = |index: usize| -> u16 {
+            match header.bit_depth {
+                16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+                8 => row[index] as u16,
+                depth => {
+                    let depth = depth as usize;
+                    let bit = index * depth;
+                    let shift = 8 - depth - bit % 8;
+                    ((row[bit / 8] >> shift) as u16) & ((1 << depth) - 1)
+                }
+            }
+        };
+        let max_sample = ((1u32 << header.bit_depth) - 1) as u16;
+        let to_u8 = |value: u16| (value as u32 * 255 / max_sample as u32) as u8;
+        let is_transparent = |values: &[u16]| {
+            transparency.as_ref().is_some_and(|key| {
+                key.len() == values.len() * 2
+                    && values.iter().enumerate().all(|(i, &value)| {
+                        u16::from_be_bytes([key[i * 2], key[i * 2 + 1]]) == value
+                    })
+            })
+        };
+
+        for x in 0..header.width as usize {
+            let base = x * channels;
+            let pixel = match header.color_type {
+                0 => {
+                    let gray = sample(base);
+                    let value = to_u8(gray);
+                    let alpha = if is_transparent(&[gray]) { 0 } else { 255 };
+                    argb(value, value, value, alpha)
+                }
+                2 => {
+                    let rgb = [sample(base), sample(base + 1), sample(base + 2)];
+                    let alpha = if is_transparent(&rgb) { 0 } else { 255 };
+                    argb(to_u8(rgb[0]), to_u8(rgb[1]), to_u8(rgb[2]), alpha)
+                }
+                3 => {
+                    let Some(&[r, g, b, a]) = palette.get(sample(base) as usize) else {
+                        bail!("PNG palette index out of range");
+                    };
+                    argb(r, g, b, a)
+                }
+                4 => {
+                    let value = to_u8(sample(base));
+                    argb(value, value, value, to_u8(sample(base + 1)))
+                }
+                _ => argb(
+                    to_u8(sample(base)),
+                    to_u8(sample(base + 1)),
+                    to_u8(sample(base + 2)),
+                    to_u8(sample(base + 3)),
+                ),
+            };
+            pixels.push(pixel);
+        }
+
+        previous.copy_from_slice(row);
+    }
+
+    Ok(from_top_down(header.width, header.height, pixels))
+}
+
+struct PngHeader {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+fn unfilter_png_row(filter: u8, row: &mut [u8], previous: &[u8], step: usize) -> Result<()> {
+    for i in 0..row.len() {
+        let left = if i >= step { row[i - step] } else { 0 };
+        let up = previous[i];
+        let up_left = if i >= step { previous[i - step] } else { 0 };
+
+        let prediction = match filter {
+            0 => 0,
+            1 => left,
+            2 => up,
+            3 => ((left as u16 + up as u16) / 2) as u8,
+            4 => paeth(left, up, up_left),
+            _ => bail!("invalid PNG filter type {}", filter),
+        };
+        row[i] = row[i].wrapping_add(prediction);
+    }
+    Ok(())
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = left as i16 + up as i16 - up_left as i16;
+    let distance_left = (estimate - left as i16).abs();
+    let distance_up = (estimate - up as i16).abs();
+    let distance_up_left = (estimate - up_left as i16).abs();
+    if distance_left <= distance_up && distance_left <= distance_up_left {
+        left
+    } else if distance_up <= distance_up_left {
+        up
+    } else {
+        up_left
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::software_canvas::SoftwareCanvas;
+    use std::path::PathBuf;
+
+    /// Path in the temp directory unique to this test process.
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("salmon_rs-{}-{}", std::process::id(), name))
+    }
+
+    /// Odd-sized image with every channel varying, bottom row first.
+    fn gradient(width: u32, height: u32) -> Vec<u32> {
+        (0..height)
+            .flat_map(|y| {
+                (0..width).map(move |x| {
+                    argb(
+                        (x * 255 / width) as u8,
+                        (y * 255 / height) as u8,
+                        (x * 31 + y * 17) as u8,
+                        (x * 7 + y * 53) as u8,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn images_round_trip() {
+        let (width, height) = (13, 7);
+        let pixels = gradient(width, height);
+
+        for format in [ImageFormat::Png, ImageFormat::Ppm, ImageFormat::Tga] {
+            let path = temp_path(&format!("round-trip.{}", format.extension()));
+            write_image(&path, width, height, &pixels).unwrap();
+            let image = read_image(&path);
+            std::fs::remove_file(&path).unwrap();
+
+            // PPM has no alpha channel and reads back opaque
+            let expected = match format {
+                ImageFormat::Ppm => pixels.iter().map(|argb| argb | 0xff00_0000).collect(),
+                _ => pixels.clone(),
+            };
+            assert_eq!(
+                image.unwrap(),
+                ImageData {
+                    width,
+                    height,
+                    pixels: expected,
+                },
+                "{format:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn render_frame_matches_golden_image() {
+        let mut canvas = SoftwareCanvas::new(64, 64);
+        canvas.render_frame();
+
+        let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/render_frame_64x64.png");
+        let expected = read_image(&golden).unwrap();
+        assert_eq!((expected.width, expected.height), (64, 64));
+        assert!(
+            canvas.pixels() == expected.pixels,
+            "render_frame no longer matches {}",
+            golden.display()
+        );
+    }
+}
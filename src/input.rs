@@ -0,0 +1,390 @@
+use crate::software_canvas::SoftwareCanvas;
+use crate::vec2::Vec2;
+use anyhow::{Context, Result, bail};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+/// Pixel scroll deltas (touchpads) are converted to lines at this rate.
+const PIXELS_PER_SCROLL_LINE: f32 = 20.0;
+
+/// A physical input an action can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+impl Binding {
+    /// Parses a winit key code name such as `KeyW`, `ArrowUp` or `Escape`,
+    /// or a mouse button: `MouseLeft`, `MouseRight`, `MouseMiddle`,
+    /// `MouseBack` or `MouseForward`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let button = match name {
+            "MouseLeft" => Some(MouseButton::Left),
+            "MouseRight" => Some(MouseButton::Right),
+            "MouseMiddle" => Some(MouseButton::Middle),
+            "MouseBack" => Some(MouseButton::Back),
+            "MouseForward" => Some(MouseButton::Forward),
+            _ => None,
+        };
+        match button {
+            Some(button) => Some(Self::Mouse(button)),
+            None => key_code_from_name(name).map(Self::Key),
+        }
+    }
+}
+
+macro_rules! key_codes {
+    ($($name:ident),* $(,)?) => {
+        fn key_code_from_name(name: &str) -> Option<KeyCode> {
+            match name {
+                $(stringify!($name) => Some(KeyCode::$name),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+key_codes!(
+    KeyA,
+    KeyB,
+    KeyC,
+    KeyD,
+    KeyE,
+    KeyF,
+    KeyG,
+    KeyH,
+    KeyI,
+    KeyJ,
+    KeyK,
+    KeyL,
+    KeyM,
+    KeyN,
+    KeyO,
+    KeyP,
+    KeyQ,
+    KeyR,
+    KeyS,
+    KeyT,
+    KeyU,
+    KeyV,
+    KeyW,
+    KeyX,
+    KeyY,
+    KeyZ,
+    Digit0,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    ArrowUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    Space,
+    Enter,
+    Escape,
+    Tab,
+    Backspace,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    ShiftLeft,
+    ShiftRight,
+    ControlLeft,
+    ControlRight,
+    AltLeft,
+    AltRight,
+    SuperLeft,
+    SuperRight,
+    Minus,
+    Equal,
+    BracketLeft,
+    BracketRight,
+    Backslash,
+    Semicolon,
+    Quote,
+    Backquote,
+    Comma,
+    Period,
+    Slash,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadAdd,
+    NumpadSubtract,
+    NumpadMultiply,
+    NumpadDivide,
+    NumpadEnter,
+    NumpadDecimal,
+);
+
+/// Named actions and the inputs that trigger them, so tools query
+/// `"move_forward"` instead of hard-coding keys.
+#[derive(Debug, Clone, Default)]
+pub struct ActionMap {
+    bindings: HashMap<String, Vec<Binding>>,
+}
+
+impl ActionMap {
+    /// Loads bindings from a config file; see `parse` for the format.
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&source).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Parses lines of the form `action = Binding, Binding, ...`, see
+    /// `Binding::from_name`. `#` starts a comment. An empty right-hand side
+    /// leaves the action unbound.
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut map = Self::default();
+
+        for (line_index, line) in source.lines().enumerate() {
+            map.parse_line(line)
+                .with_context(|| format!("line {}: {}", line_index + 1, line.trim()))?;
+        }
+
+        Ok(map)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<()> {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            return Ok(());
+        }
+
+        let Some((action, bindings)) = line.split_once('=') else {
+            bail!("expected 'action = bindings'");
+        };
+        let action = action.trim();
+        if action.is_empty() || action.contains(char::is_whitespace) {
+            bail!("invalid action name '{}'", action);
+        }
+
+        let bindings = bindings
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                Binding::from_name(name).with_context(|| format!("unknown input '{}'", name))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.bindings.insert(action.to_string(), bindings);
+        Ok(())
+    }
+
+    /// Replaces the bindings of every action `other` defines.
+    pub fn merge(&mut self, other: ActionMap) {
+        self.bindings.extend(other.bindings);
+    }
+
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        let bindings = self.bindings.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind(&mut self, action: &str, binding: Binding) {
+        if let Some(bindings) = self.bindings.get_mut(action) {
+            bindings.retain(|&bound| bound != binding);
+        }
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.bindings.get(action).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Keyboard and mouse state built from winit window events.
+///
+/// "Pressed" and "released" cover the current frame: they are set as events
+/// arrive and cleared by `end_frame`, together with the scroll and cursor
+/// deltas.
+#[derive(Debug, Default)]
+pub struct Input {
+    down: HashSet<Binding>,
+    pressed: HashSet<Binding>,
+    released: HashSet<Binding>,
+    /// Cursor position in physical window pixels, origin top-left
+    cursor: Option<Vec2>,
+    cursor_delta: Vec2,
+    /// Scroll in lines; positive y scrolls up / away from the user
+    scroll: Vec2,
+    actions: ActionMap,
+}
+
+impl Input {
+    pub fn new(actions: ActionMap) -> Self {
+        Self {
+            actions,
+            ..Self::default()
+        }
+    }
+
+    pub fn actions(&self) -> &ActionMap {
+        &self.actions
+    }
+
+    pub fn actions_mut(&mut self) -> &mut ActionMap {
+        &mut self.actions
+    }
+
+    /// Updates the state from a window event; other events are ignored.
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                if let PhysicalKey::Code(code) = event.physical_key
+                    && !event.repeat
+                {
+                    self.set_state(Binding::Key(code), event.state);
+                }
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                self.set_state(Binding::Mouse(*button), *state);
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = Vec2::new(position.x as f32, position.y as f32);
+                if let Some(previous) = self.cursor {
+                    self.cursor_delta += position - previous;
+                }
+                self.cursor = Some(position);
+            }
+            WindowEvent::CursorLeft { .. } => self.cursor = None,
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => Vec2::new(*x, *y),
+                    MouseScrollDelta::PixelDelta(position) => {
+                        Vec2::new(position.x as f32, position.y as f32) / PIXELS_PER_SCROLL_LINE
+                    }
+                };
+                self.scroll += lines;
+            }
+            // Keys held while the window loses focus never report a release
+            WindowEvent::Focused(false) => {
+                self.released.extend(self.down.drain());
+            }
+            _ => {}
+        }
+    }
+
+    fn set_state(&mut self, binding: Binding, state: ElementState) {
+        match state {
+            ElementState::Pressed => {
+                if self.down.insert(binding) {
+                    self.pressed.insert(binding);
+                }
+            }
+            ElementState::Released => {
+                if self.down.remove(&binding) {
+                    self.released.insert(binding);
+                }
+            }
+        }
+    }
+
+    /// Clears the per-frame state; call once every frame has been handled.
+    pub fn end_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.cursor_delta = Vec2::ZERO;
+        self.scroll = Vec2::ZERO;
+    }
+
+    pub fn is_down(&self, binding: Binding) -> bool {
+        self.down.contains(&binding)
+    }
+
+    pub fn was_pressed(&self, binding: Binding) -> bool {
+        self.pressed.contains(&binding)
+    }
+
+    pub fn was_released(&self, binding: Binding) -> bool {
+        self.released.contains(&binding)
+    }
+
+    pub fn is_key_down(&self, key: KeyCode) -> bool {
+        self.is_down(Binding::Key(key))
+    }
+
+    pub fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.is_down(Binding::Mouse(button))
+    }
+
+    /// Whether any input bound to `action` is held.
+    pub fn is_action_down(&self, action: &str) -> bool {
+        self.actions
+            .bindings(action)
+            .iter()
+            .any(|&binding| self.is_down(binding))
+    }
+
+    /// Whether any input bound to `action` went down this frame.
+    pub fn was_action_pressed(&self, action: &str) -> bool {
+        self.actions
+            .bindings(action)
+            .iter()
+            .any(|&binding| self.was_pressed(binding))
+    }
+
+    /// Whether any input bound to `action` went up this frame.
+    pub fn was_action_released(&self, action: &str) -> bool {
+        self.actions
+            .bindings(action)
+            .iter()
+            .any(|&binding| self.was_released(binding))
+    }
+
+    /// Cursor position in physical window pixels, origin top-left, or `None`
+    /// while it is outside the window.
+    pub fn cursor_position(&self) -> Option<Vec2> {
+        self.cursor
+    }
+
+    /// Cursor position in canvas coordinates, origin bottom-left, or `None`
+    /// when it isn't over the canvas. See `SoftwareCanvas::window_to_canvas`.
+    pub fn cursor_canvas_position(&self, canvas: &SoftwareCanvas) -> Option<Vec2> {
+        let cursor = self.cursor?;
+        canvas.window_to_canvas(cursor.x, cursor.y)
+    }
+
+    /// Cursor movement this frame in window pixels, y pointing down.
+    pub fn cursor_delta(&self) -> Vec2 {
+        self.cursor_delta
+    }
+
+    /// Scroll this frame in lines.
+    pub fn scroll_delta(&self) -> Vec2 {
+        self.scroll
+    }
+}
@@ -0,0 +1,28 @@
+pub mod bezier;
+pub mod blend;
+pub mod blit;
+pub mod camera;
+pub mod clip;
+pub mod frame_stats;
+pub mod image_io;
+pub mod input;
+mod line;
+pub mod mat3;
+pub mod mat4;
+pub mod mesh;
+pub mod paint;
+mod pipeline;
+mod rasterizer;
+pub mod resolution;
+pub mod shader;
+mod shapes;
+pub mod simd;
+pub mod software_canvas;
+pub mod stroke;
+pub mod texture;
+pub mod timing;
+pub mod vec2;
+pub mod vec3;
+pub mod vec4;
+mod worker_pool;
+mod zlib;
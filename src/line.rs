@@ -0,0 +1,269 @@
+//! Line rasterization: integer Bresenham and Xiaolin Wu anti-aliasing, both
+//! clipped to the canvas with Liang–Barsky before any pixel is visited.
+
+/// Clips the segment `a -> b` to the rectangle `[min, max]` and returns the
+/// visible parameter range `t0..=t1` along it.
+fn liang_barsky(
+    a: (f64, f64),
+    b: (f64, f64),
+    min: (f64, f64),
+    max: (f64, f64),
+) -> Option<(f64, f64)> {
+    let delta = (b.0 - a.0, b.1 - a.1);
+    let (mut t0, mut t1) = (0.0f64, 1.0f64);
+
+    // Each pair is (p, q): the segment is inside the boundary where p * t <= q
+    let boundaries = [
+        (-delta.0, a.0 - min.0),
+        (delta.0, max.0 - a.0),
+        (-delta.1, a.1 - min.1),
+        (delta.1, max.1 - a.1),
+    ];
+    for (p, q) in boundaries {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+        }
+    }
+
+    (t0 <= t1).then_some((t0, t1))
+}
+
+/// Calls `plot(x, y)` for every pixel of the Bresenham line from `(x1, y1)` to
+/// `(x2, y2)` that lies within `width x height`. The line is clipped up front
+/// and the error term is seeded at the first visible step, so the pixels
+/// visited match the unclipped line exactly without walking off-canvas spans.
+pub(crate) fn bresenham(
+    (x1, y1): (i32, i32),
+    (x2, y2): (i32, i32),
+    (width, height): (u32, u32),
+    mut plot: impl FnMut(u32, u32),
+) {
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    // Pixel centers sit on integer coordinates here; clip with half a pixel of margin
+    let Some((t0, t1)) = liang_barsky(
+        (x1 as f64, y1 as f64),
+        (x2 as f64, y2 as f64),
+        (-0.5, -0.5),
+        (width as f64 - 0.5, height as f64 - 0.5),
+    ) else {
+        return;
+    };
+
+    let (dx, dy) = (x2 as i64 - x1 as i64, y2 as i64 - y1 as i64);
+    let steep = dy.abs() > dx.abs();
+    // Walk the major axis u one pixel at a time; the minor axis v follows the error term
+    let (u1, v1, du, dv) = if steep {
+        (y1 as i64, x1 as i64, dy, dx)
+    } else {
+        (x1 as i64, y1 as i64, dx, dy)
+    };
+    let (step_u, step_v) = (du.signum(), dv.signum());
+    let (length, rise) = (du.abs(), dv.abs());
+
+    // Visible step range, widened by one step to absorb rounding at the clip edges
+    let first = ((t0 * length as f64).floor() as i64 - 1).max(0);
+    let last = ((t1 * length as f64).ceil() as i64 + 1).min(length);
+
+    // v offset after k steps is floor((2 * rise * k + length) / (2 * length)),
+    // seeded in i128 since 2 * rise * k overflows i64 for lines near the i32 limits
+    let denominator = 2 * length.max(1);
+    let numerator = 2 * rise as i128 * first as i128 + length as i128;
+    let mut offset = (numerator / denominator as i128) as i64;
+    let mut error = (numerator % denominator as i128) as i64;
+
+    for k in first..=last {
+        let (u, v) = (u1 + step_u * k, v1 + step_v * offset);
+        let (x, y) = if steep { (v, u) } else { (u, v) };
+        if (0..width as i64).contains(&x) && (0..height as i64).contains(&y) {
+            plot(x as u32, y as u32);
+        }
+
+        error += 2 * rise;
+        if error >= denominator {
+            error -= denominator;
+            offset += 1;
+        }
+    }
+}
+
+/// Calls `plot(x, y, coverage)` for the pixels of an anti-aliased line between
+/// two points with pixel centers at integer coordinates, using Xiaolin Wu's
+/// algorithm. Pixels outside `width x height` are skipped.
+pub(crate) fn wu(
+    (x1, y1): (f32, f32),
+    (x2, y2): (f32, f32),
+    (width, height): (u32, u32),
+    mut plot: impl FnMut(u32, u32, f32),
+) {
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    // Clip with a pixel of margin, since coverage bleeds into neighbors
+    let Some((t0, t1)) = liang_barsky(
+        (x1 as f64, y1 as f64),
+        (x2 as f64, y2 as f64),
+        (-1.5, -1.5),
+        (width as f64 + 0.5, height as f64 + 0.5),
+    ) else {
+        return;
+    };
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    let (t0, t1) = (t0 as f32, t1 as f32);
+    let (mut ax, mut ay, mut bx, mut by) = (x1 + dx * t0, y1 + dy * t0, x1 + dx * t1, y1 + dy * t1);
+
+    let steep = dy.abs() > dx.abs();
+    if steep {
+        std::mem::swap(&mut ax, &mut ay);
+        std::mem::swap(&mut bx, &mut by);
+    }
+    if ax > bx {
+        std::mem::swap(&mut ax, &mut bx);
+        std::mem::swap(&mut ay, &mut by);
+    }
+
+    let mut emit = |u: f32, v: f32, coverage: f32| {
+        let (x, y) = if steep { (v, u) } else { (u, v) };
+        if coverage > 0.0 && (0.0..width as f32).contains(&x) && (0.0..height as f32).contains(&y) {
+            plot(x as u32, y as u32, coverage);
+        }
+    };
+
+    // Fractional part that stays positive for negative coordinates
+    let fpart = |value: f32| value - value.floor();
+    let gradient = if bx - ax == 0.0 {
+        1.0
+    } else {
+        (by - ay) / (bx - ax)
+    };
+
+    // Endpoint columns, and how much of each the line spans
+    let u_start = ax.round();
+    let v_start = ay + gradient * (u_start - ax);
+    let start_gap = 1.0 - fpart(ax + 0.5);
+    let u_end = bx.round();
+    let end_gap = fpart(bx + 0.5);
+
+    // Both endpoints in one column: plot it once so blending doesn't cover it
+    // twice, covering as much as the line is long
+    if u_start == u_end {
+        let gap = bx - ax;
+        let v_floor = v_start.floor();
+        emit(u_start, v_floor, (1.0 - fpart(v_start)) * gap);
+        emit(u_start, v_floor + 1.0, fpart(v_start) * gap);
+        return;
+    }
+
+    // First endpoint
+    let v_floor = v_start.floor();
+    emit(u_start, v_floor, (1.0 - fpart(v_start)) * start_gap);
+    emit(u_start, v_floor + 1.0, fpart(v_start) * start_gap);
+    let mut v = v_start + gradient;
+
+    // Second endpoint
+    let v_end = by + gradient * (u_end - bx);
+    let v_floor = v_end.floor();
+    emit(u_end, v_floor, (1.0 - fpart(v_end)) * end_gap);
+    emit(u_end, v_floor + 1.0, fpart(v_end) * end_gap);
+
+    // Span between the endpoints
+    let mut u = u_start + 1.0;
+    while u < u_end {
+        let v_floor = v.floor();
+        emit(u, v_floor, 1.0 - (v - v_floor));
+        emit(u, v_floor + 1.0, v - v_floor);
+        v += gradient;
+        u += 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bresenham_pixels(a: (i32, i32), b: (i32, i32), size: (u32, u32)) -> Vec<(u32, u32)> {
+        let mut pixels = Vec::new();
+        bresenham(a, b, size, |x, y| pixels.push((x, y)));
+        pixels
+    }
+
+    /// Walks every step of the unclipped line, keeping the pixels on the canvas.
+    fn unclipped_pixels(a: (i32, i32), b: (i32, i32), size: (u32, u32)) -> Vec<(u32, u32)> {
+        let (dx, dy) = (b.0 as i64 - a.0 as i64, b.1 as i64 - a.1 as i64);
+        let steep = dy.abs() > dx.abs();
+        let (du, dv) = if steep { (dy, dx) } else { (dx, dy) };
+        (0..=du.abs())
+            .filter_map(|k| {
+                let offset = (2 * dv.abs() * k + du.abs()) / (2 * du.abs().max(1));
+                let (u, v) = (k * du.signum(), offset * dv.signum());
+                let (x, y) = if steep { (v, u) } else { (u, v) };
+                let x = u32::try_from(a.0 as i64 + x).ok().filter(|&x| x < size.0)?;
+                let y = u32::try_from(a.1 as i64 + y).ok().filter(|&y| y < size.1)?;
+                Some((x, y))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clipped_bresenham_matches_the_unclipped_line() {
+        let size = (40, 30);
+        let lines = [
+            ((5, 5), (30, 12)),
+            ((-50, -20), (90, 70)),
+            ((20, -100), (10, 200)),
+            ((-7, 29), (47, 0)),
+            ((39, 3), (-300, 17)),
+            ((12, 12), (12, 12)),
+        ];
+        for (a, b) in lines {
+            assert_eq!(
+                bresenham_pixels(a, b, size),
+                unclipped_pixels(a, b, size),
+                "{a:?} {b:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn bresenham_handles_endpoints_near_the_i32_limits() {
+        // Crosses the canvas along its diagonal
+        let pixels = bresenham_pixels(
+            (-2_000_000_000, -1_999_999_990),
+            (2_000_000_000, 1_999_999_990),
+            (64, 64),
+        );
+        let diagonal: Vec<_> = (0..64).map(|i| (i, i)).collect();
+        assert_eq!(pixels, diagonal);
+
+        let pixels = bresenham_pixels((i32::MIN, i32::MAX), (i32::MAX, i32::MIN), (64, 64));
+        assert!(pixels.is_empty());
+    }
+
+    #[test]
+    fn sub_pixel_wu_segment_covers_its_length() {
+        let segments = [
+            ((10.2, 5.0), (10.3, 5.0)),
+            ((10.1, 5.25), (10.4, 5.25)),
+            ((3.0, 7.3), (3.25, 7.1)),
+            ((6.0, 2.4), (6.1, 2.1)),
+        ];
+        for (a, b) in segments {
+            let mut total = 0.0;
+            wu(a, b, (16, 16), |_, _, coverage| total += coverage);
+            let length = (b.0 - a.0).abs().max((b.1 - a.1).abs());
+            assert!((total - length).abs() < 1e-5, "{a:?} {b:?}: {total}");
+        }
+    }
+}
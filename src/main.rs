@@ -1,32 +1,104 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use winit::{
     application::ApplicationHandler,
-    event::{KeyEvent, WindowEvent},
+    event::WindowEvent,
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
-    keyboard::{KeyCode, PhysicalKey},
     window::{Window, WindowId},
 };
 
-mod software_canvas;
-mod timing;
-mod vec4;
+use salmon_rs::frame_stats::FrameStats;
+use salmon_rs::image_io::ImageFormat;
+use salmon_rs::input::{ActionMap, Input};
+use salmon_rs::paint::PaintEditor;
+use salmon_rs::resolution::ResolutionController;
+use salmon_rs::software_canvas::{CanvasSizing, SoftwareCanvas};
+use salmon_rs::timing::FrameTiming;
+use salmon_rs::vec2::Vec2;
+use salmon_rs::vec4::Vec4;
 
-use software_canvas::SoftwareCanvas;
-use timing::FrameTiming;
+mod options;
+mod scene;
+
+use options::Options;
+use scene::Scene;
 
 struct App {
     /// The main window handle - wrapped in Arc for sharing with softbuffer
     window: Option<std::sync::Arc<Window>>,
 
-    /// Fixed 64x64 canvas for all drawing operations with integrated graphics
+    /// Canvas for all drawing operations with integrated graphics
     canvas: SoftwareCanvas,
 
     /// Frame timing and FPS management
     timing: FrameTiming,
+
+    /// Adjusts the canvas resolution to hold a target frame time, if enabled
+    resolution: Option<ResolutionController>,
+
+    /// Keyboard and mouse state with the action bindings
+    input: Input,
+
+    /// Simulated time, advanced in fixed steps by `update`
+    simulation_time: Duration,
+
+    /// Mesh viewed with the interactive camera, drawn instead of the 2D frame
+    scene: Option<Scene>,
+
+    /// Window position where the `pick` action went down, until it's released
+    pick_start: Option<Vec2>,
+
+    /// Pixel editor and the image it saves to, when painting instead of
+    /// drawing the 2D frame
+    paint: Option<(PaintEditor, PathBuf)>,
 }
 
+/// Bindings used unless overridden with `--bindings`.
+const DEFAULT_BINDINGS: &str = "\
+quit = Escape
+pick = MouseLeft
+camera_rotate = MouseLeft
+camera_pan = MouseMiddle
+camera_look = MouseRight
+camera_toggle = KeyC
+orbit_left = ArrowLeft
+orbit_right = ArrowRight
+orbit_up = ArrowUp
+orbit_down = ArrowDown
+move_forward = KeyW
+move_back = KeyS
+move_left = KeyA
+move_right = KeyD
+move_up = KeyE, Space
+move_down = KeyQ
+move_fast = ShiftLeft
+paint = MouseLeft
+undo = KeyZ
+redo = KeyY
+save = F2
+load = F3
+tool_pencil = KeyB
+tool_fill = KeyF
+tool_line = KeyL
+tool_rect = KeyR
+tool_eyedropper = KeyI
+color_1 = Digit1
+color_2 = Digit2
+color_3 = Digit3
+color_4 = Digit4
+color_5 = Digit5
+color_6 = Digit6
+color_7 = Digit7
+color_8 = Digit8
+";
+
 const DEFAULT_SIZE: (u32, u32) = (640, 640);
 
+/// Farthest the cursor may move, in window pixels, between pressing and
+/// releasing `pick` for the click to count as a pick rather than a drag.
+const PICK_DRAG_TOLERANCE: f32 = 3.0;
+
 impl ApplicationHandler for App {
     /// Called when the application is resumed or initially started.
     /// This is where we create the window and initialize graphics.
@@ -48,6 +120,10 @@ impl ApplicationHandler for App {
         self.canvas
             .initialize_graphics(window.clone())
             .expect("Failed to initialize graphics");
+        let size = window.inner_size();
+        self.canvas.resize_to_window((size.width, size.height));
+        self.timing
+            .set_monitor_refresh_rate(monitor_refresh_rate(&window));
 
         self.window = Some(window.clone());
 
@@ -65,30 +141,74 @@ impl ApplicationHandler for App {
             WindowEvent::CloseRequested => {
                 event_loop.exit();
             }
-            // User pressed the Escape key - also exit the application
-            WindowEvent::KeyboardInput {
-                event:
-                    KeyEvent {
-                        physical_key: PhysicalKey::Code(KeyCode::Escape),
-                        ..
-                    },
-                ..
-            } => {
-                event_loop.exit();
+            // Keyboard and mouse input - track it and react to bound actions
+            WindowEvent::KeyboardInput { .. }
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::CursorMoved { .. }
+            | WindowEvent::CursorLeft { .. }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::Focused(_) => {
+                self.input.handle_event(&event);
+                if self.input.was_action_pressed("quit") {
+                    event_loop.exit();
+                }
             }
             // Window needs to be redrawn - update FPS counter and render a new frame
             WindowEvent::RedrawRequested => {
-                if let Some(fps) = self.timing.update_fps() {
-                    if let Some(window) = &self.window {
-                        window.set_title(&format!("Salmon RS - FPS: {:.1}", fps));
+                if let Some(fps) = self.timing.update_fps()
+                    && let Some(window) = &self.window
+                {
+                    let mut title = format!("Salmon RS - FPS: {:.1}", fps);
+                    if let Some(summary) = self.timing.stats().summary() {
+                        title += &format!(" (1% low: {:.1})", summary.low_1_percent_fps);
+                    }
+                    if let Some(controller) = &self.resolution {
+                        title += &format!(" - Scale: {:.0}%", controller.scale() * 100.0);
+                    }
+                    if let Some((editor, _)) = &self.paint {
+                        title += &format!(" - Tool: {:?}", editor.tool());
                     }
+                    window.set_title(&title);
+                }
+
+                // Step the simulation at its fixed rate, independent of the frame rate
+                let dt = self.timing.tick_duration();
+                for _ in 0..self.timing.advance_simulation() {
+                    self.update(dt);
+                }
+
+                if self.scene.is_some() {
+                    self.update_pick();
                 }
+                // Camera movement follows the frame rate, scaled by the frame delta.
+                // Presses last the whole frame, so toggle here rather than per event.
+                if let Some(scene) = &mut self.scene {
+                    if self.input.was_action_pressed("camera_toggle") {
+                        scene.toggle_mode();
+                        println!("Camera: {:?}", scene.mode());
+                    }
+                    scene.update(&self.input, self.timing.frame_delta());
+                }
+                self.update_paint();
+
+                let render_start = Instant::now();
                 self.draw();
+                self.update_resolution(render_start.elapsed());
+                self.input.end_frame();
             }
-            // Window was resized by the user - trigger a redraw to fill the new size
-            WindowEvent::Resized(_) => {
+            // Window was resized by the user - follow it with the canvas if
+            // configured to, then redraw to fill the new size
+            WindowEvent::Resized(size) => {
+                self.canvas.resize_to_window((size.width, size.height));
                 self.draw();
             }
+            // The window may have moved to a monitor with a different refresh rate
+            WindowEvent::Moved(_) | WindowEvent::ScaleFactorChanged { .. } => {
+                if let Some(window) = &self.window {
+                    self.timing
+                        .set_monitor_refresh_rate(monitor_refresh_rate(window));
+                }
+            }
             // Ignore all other window events (mouse, keyboard, focus, etc.)
             _ => {}
         }
@@ -97,7 +217,7 @@ impl ApplicationHandler for App {
     /// Called when the event loop is about to wait for new events.
     /// In polling mode, this is where we implement FPS limiting and request continuous redraws.
     fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
-        // Apply FPS limiting
+        // Wait for the next frame deadline
         self.timing.apply_fps_limit();
 
         // Request redraw continuously for polling mode
@@ -108,11 +228,120 @@ impl ApplicationHandler for App {
 }
 
 impl App {
-    fn new() -> Self {
-        Self {
+    fn new(options: &Options, actions: ActionMap, scene: Option<Scene>) -> Result<Self> {
+        let (width, height) = options.canvas_size;
+        let mut canvas = SoftwareCanvas::new(width, height);
+
+        // Painting starts from the image being edited, or a transparent canvas
+        let paint = match &options.paint_path {
+            Some(path) => {
+                if path.exists() {
+                    canvas.load_image(path)?;
+                } else {
+                    canvas.clear(Vec4::new(0.0, 0.0, 0.0, 0.0));
+                }
+                Some((PaintEditor::new(), path.clone()))
+            }
+            None => None,
+        };
+        canvas.set_scale_mode(options.scale_mode);
+        canvas.set_scale_filter(options.scale_filter);
+        // Only the mesh scene draws objects worth picking
+        canvas.set_picking(scene.is_some());
+        if let Some(fraction) = options.follow_window {
+            canvas.set_sizing(CanvasSizing::FollowWindow(fraction));
+        }
+
+        // Dynamic resolution drives the fraction of the window the canvas follows
+        let resolution = options.target_frame_time.map(|target| {
+            let max_scale = options.follow_window.unwrap_or(1.0);
+            let controller = ResolutionController::new(target, options.min_scale, max_scale);
+            canvas.set_sizing(CanvasSizing::FollowWindow(controller.scale()));
+            controller
+        });
+
+        let mut timing = FrameTiming::new();
+        timing.set_fps_limit(options.fps_limit);
+        timing.set_tick_rate(options.tick_rate);
+        *timing.stats_mut() = FrameStats::new(options.stats_frames);
+
+        Ok(Self {
             window: None,
-            canvas: SoftwareCanvas::new(64, 64),
-            timing: FrameTiming::new(),
+            canvas,
+            timing,
+            resolution,
+            input: Input::new(actions),
+            simulation_time: Duration::ZERO,
+            scene,
+            pick_start: None,
+            paint,
+        })
+    }
+
+    /// Advances the simulation by one fixed step of `dt`. Runs zero or more
+    /// times per frame, so simulation state never depends on the frame rate.
+    fn update(&mut self, dt: Duration) {
+        self.simulation_time += dt;
+    }
+
+    /// Feeds the render time of the last frame to the dynamic resolution
+    /// controller and resizes the canvas when it picks a new scale.
+    fn update_resolution(&mut self, render_time: Duration) {
+        let (Some(controller), Some(window)) = (&mut self.resolution, &self.window) else {
+            return;
+        };
+        if let Some(scale) = controller.record_frame(render_time) {
+            let size = window.inner_size();
+            self.canvas.set_sizing(CanvasSizing::FollowWindow(scale));
+            self.canvas.resize_to_window((size.width, size.height));
+        }
+    }
+
+    /// Applies this frame's input to the pixel editor, if painting, and saves
+    /// or loads its image on the `save` and `load` actions.
+    fn update_paint(&mut self) {
+        let Some((editor, path)) = &mut self.paint else {
+            return;
+        };
+        editor.update(&self.input, &mut self.canvas);
+        if self.input.was_action_pressed("save") {
+            match editor.save(&self.canvas, path) {
+                Ok(()) => println!("Saved {}", path.display()),
+                Err(e) => println!("Failed to save: {:#}", e),
+            }
+        }
+        if self.input.was_action_pressed("load") {
+            match editor.load(&mut self.canvas, path) {
+                Ok(()) => println!("Loaded {}", path.display()),
+                Err(e) => println!("Failed to load: {:#}", e),
+            }
+        }
+    }
+
+    /// Reports a pick when the `pick` action is released where it went down.
+    /// Picking shares a button with camera drags, which move the cursor further
+    /// than `PICK_DRAG_TOLERANCE` and so don't pick.
+    fn update_pick(&mut self) {
+        let cursor = self.input.cursor_position();
+        if self.input.was_action_pressed("pick") {
+            self.pick_start = cursor;
+        }
+        if self.input.was_action_released("pick")
+            && let (Some(start), Some(cursor)) = (self.pick_start.take(), cursor)
+            && (cursor - start).length() <= PICK_DRAG_TOLERANCE
+        {
+            self.report_pick();
+        }
+    }
+
+    /// Prints what was drawn under the cursor in the last frame.
+    fn report_pick(&self) {
+        let Some(cursor) = self.input.cursor_position() else {
+            return;
+        };
+        match self.canvas.pick_window(cursor.x, cursor.y) {
+            Some(id) => println!("Picked object {} triangle {}", id.object, id.primitive),
+            None => println!("Picked nothing"),
         }
     }
 
@@ -124,13 +353,18 @@ impl App {
             }
 
             // Ensure surface matches current window size
-            if let Err(e) = self.canvas.ensure_surface_size(&window) {
+            if let Err(e) = self.canvas.ensure_surface_size(window) {
                 println!("Failed to ensure surface size: {}", e);
                 return;
             }
 
             // Render the frame content
-            self.canvas.render_frame();
+            // In paint mode the canvas already holds the frame
+            if let Some(scene) = &self.scene {
+                scene.draw(&mut self.canvas);
+            } else if self.paint.is_none() {
+                self.canvas.render_frame();
+            }
 
             // Present the frame
             if let Err(e) = self.canvas.present_frame() {
@@ -140,11 +374,70 @@ impl App {
     }
 }
 
+/// Refresh rate of the monitor `window` is on, in Hz, if the platform reports it.
+fn monitor_refresh_rate(window: &Window) -> Option<f32> {
+    let millihertz = window.current_monitor()?.refresh_rate_millihertz()?;
+    Some(millihertz as f32 / 1000.0)
+}
+
+/// Renders frames into the offscreen canvas and writes each one to `out_dir`.
+/// Needs no display, so it runs on CI machines and servers.
+fn run_headless(
+    frames: u32,
+    out_dir: &Path,
+    format: ImageFormat,
+    canvas_size: (u32, u32),
+    scene: Option<&Scene>,
+) -> Result<()> {
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+
+    let mut canvas = SoftwareCanvas::new(canvas_size.0, canvas_size.1);
+    for frame in 0..frames {
+        match scene {
+            Some(scene) => scene.draw(&mut canvas),
+            None => canvas.render_frame(),
+        }
+
+        let path = out_dir.join(format!("frame_{:04}.{}", frame, format.extension()));
+        canvas.save_image(&path)?;
+    }
+    Ok(())
+}
+
 fn main() -> Result<()> {
+    let options = Options::parse(std::env::args().skip(1))?;
+    let scene = match &options.mesh_path {
+        Some(path) => Some(Scene::load(path, options.camera_mode)?),
+        None => None,
+    };
+    if options.headless {
+        return run_headless(
+            options.frames,
+            &options.out_dir,
+            options.format,
+            options.canvas_size,
+            scene.as_ref(),
+        );
+    }
+
     let event_loop = EventLoop::new()?;
 
     event_loop.set_control_flow(ControlFlow::Poll);
-    let mut app = App::new();
+    let mut actions = ActionMap::parse(DEFAULT_BINDINGS)?;
+    if let Some(path) = &options.bindings_path {
+        actions.merge(ActionMap::load(path)?);
+    }
+
+    let mut app = App::new(&options, actions, scene)?;
     event_loop.run_app(&mut app)?;
+
+    let stats = app.timing.stats();
+    if let Some(summary) = stats.summary() {
+        println!("{}", summary);
+    }
+    if let Some(path) = &options.stats_path {
+        stats.write_csv(path)?;
+    }
     Ok(())
 }
@@ -0,0 +1,116 @@
+use crate::mat4::Mat4;
+use crate::vec3::Vec3;
+use std::ops::Mul;
+
+/// Row-major 3x3 matrix (`m[row][col]`) acting on column vectors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat3 {
+    pub m: [[f32; 3]; 3],
+}
+
+impl Mat3 {
+    pub const IDENTITY: Self = Self {
+        m: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+    };
+
+    pub const fn from_rows(m: [[f32; 3]; 3]) -> Self {
+        Self { m }
+    }
+
+    pub fn from_cols(x: Vec3, y: Vec3, z: Vec3) -> Self {
+        Self::from_rows([[x.x, y.x, z.x], [x.y, y.y, z.y], [x.z, y.z, z.z]])
+    }
+
+    /// The upper-left 3x3 block of a 4x4 transform.
+    pub fn from_mat4(matrix: &Mat4) -> Self {
+        let m = &matrix.m;
+        Self::from_rows([
+            [m[0][0], m[0][1], m[0][2]],
+            [m[1][0], m[1][1], m[1][2]],
+            [m[2][0], m[2][1], m[2][2]],
+        ])
+    }
+
+    /// Matrix that transforms normals consistently with the 3x3 part of
+    /// `model`: the inverse transpose, falling back to identity when singular.
+    pub fn normal_matrix(model: &Mat4) -> Self {
+        Self::from_mat4(model)
+            .inverse()
+            .map(Self::transpose)
+            .unwrap_or(Self::IDENTITY)
+    }
+
+    pub fn scale(scale: Vec3) -> Self {
+        Self::from_rows([
+            [scale.x, 0.0, 0.0],
+            [0.0, scale.y, 0.0],
+            [0.0, 0.0, scale.z],
+        ])
+    }
+
+    pub fn row(&self, row: usize) -> Vec3 {
+        Vec3::new(self.m[row][0], self.m[row][1], self.m[row][2])
+    }
+
+    pub fn col(&self, col: usize) -> Vec3 {
+        Vec3::new(self.m[0][col], self.m[1][col], self.m[2][col])
+    }
+
+    pub fn transpose(self) -> Self {
+        let m = &self.m;
+        Self::from_rows([
+            [m[0][0], m[1][0], m[2][0]],
+            [m[0][1], m[1][1], m[2][1]],
+            [m[0][2], m[1][2], m[2][2]],
+        ])
+    }
+
+    pub fn determinant(&self) -> f32 {
+        self.row(0).dot(self.row(1).cross(self.row(2)))
+    }
+
+    /// The inverse matrix, or `None` if the matrix is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let (r0, r1, r2) = (self.row(0), self.row(1), self.row(2));
+        let determinant = r0.dot(r1.cross(r2));
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+
+        // The columns of the inverse are the cross products of the rows
+        let inverse_determinant = 1.0 / determinant;
+        Some(Self::from_cols(
+            r1.cross(r2) * inverse_determinant,
+            r2.cross(r0) * inverse_determinant,
+            r0.cross(r1) * inverse_determinant,
+        ))
+    }
+}
+
+impl Default for Mat3 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul for Mat3 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let mut m = [[0.0; 3]; 3];
+        for (row, out) in m.iter_mut().enumerate() {
+            for (col, value) in out.iter_mut().enumerate() {
+                *value = self.row(row).dot(other.col(col));
+            }
+        }
+        Self::from_rows(m)
+    }
+}
+
+impl Mul<Vec3> for Mat3 {
+    type Output = Vec3;
+
+    fn mul(self, v: Vec3) -> Vec3 {
+        Vec3::new(self.row(0).dot(v), self.row(1).dot(v), self.row(2).dot(v))
+    }
+}
@@ -0,0 +1,357 @@
+use crate::vec3::Vec3;
+use crate::vec4::Vec4;
+use std::ops::Mul;
+
+/// Row-major 4x4 matrix (`m[row][col]`) acting on column vectors, so
+/// `a * b` applies `b` first. Projections follow the OpenGL conventions:
+/// right-handed view space looking down `-z`, clip-space depth in `[-w, w]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    pub m: [[f32; 4]; 4],
+}
+
+impl Mat4 {
+    pub const IDENTITY: Self = Self {
+        m: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub const fn from_rows(m: [[f32; 4]; 4]) -> Self {
+        Self { m }
+    }
+
+    pub fn translation(offset: Vec3) -> Self {
+        Self::from_rows([
+            [1.0, 0.0, 0.0, offset.x],
+            [0.0, 1.0, 0.0, offset.y],
+            [0.0, 0.0, 1.0, offset.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scale(scale: Vec3) -> Self {
+        Self::from_rows([
+            [scale.x, 0.0, 0.0, 0.0],
+            [0.0, scale.y, 0.0, 0.0],
+            [0.0, 0.0, scale.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_x(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::from_rows([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, cos, -sin, 0.0],
+            [0.0, sin, cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_y(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::from_rows([
+            [cos, 0.0, sin, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [-sin, 0.0, cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_z(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::from_rows([
+            [cos, -sin, 0.0, 0.0],
+            [sin, cos, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Counter-clockwise rotation of `angle` radians about `axis`.
+    pub fn rotation(axis: Vec3, angle: f32) -> Self {
+        let Vec3 { x, y, z } = axis.normalize();
+        let (sin, cos) = angle.sin_cos();
+        let t = 1.0 - cos;
+        Self::from_rows([
+            [
+                t * x * x + cos,
+                t * x * y - sin * z,
+                t * x * z + sin * y,
+                0.0,
+            ],
+            [
+                t * x * y + sin * z,
+                t * y * y + cos,
+                t * y * z - sin * x,
+                0.0,
+            ],
+            [
+                t * x * z - sin * y,
+                t * y * z + sin * x,
+                t * z * z + cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// View matrix for a camera at `eye` looking at `target`.
+    pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        let forward = (target - eye).normalize();
+        let side = forward.cross(up).normalize();
+        let up = side.cross(forward);
+        Self::from_rows([
+            [side.x, side.y, side.z, -side.dot(eye)],
+            [up.x, up.y, up.z, -up.dot(eye)],
+            [-forward.x, -forward.y, -forward.z, forward.dot(eye)],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Perspective projection with a vertical field of view in radians.
+    pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Self {
+        let focal = 1.0 / (fov_y * 0.5).tan();
+        let depth = near - far;
+        Self::from_rows([
+            [focal / aspect, 0.0, 0.0, 0.0],
+            [0.0, focal, 0.0, 0.0],
+            [0.0, 0.0, (far + near) / depth, 2.0 * far * near / depth],
+            [0.0, 0.0, -1.0, 0.0],
+        ])
+    }
+
+    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self {
+        let width = right - left;
+        let height = top - bottom;
+        let depth = far - near;
+        Self::from_rows([
+            [2.0 / width, 0.0, 0.0, -(right + left) / width],
+            [0.0, 2.0 / height, 0.0, -(top + bottom) / height],
+            [0.0, 0.0, -2.0 / depth, -(far + near) / depth],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn row(&self, row: usize) -> Vec4 {
+        let r = self.m[row];
+        Vec4::new(r[0], r[1], r[2], r[3])
+    }
+
+    pub fn col(&self, col: usize) -> Vec4 {
+        Vec4::new(
+            self.m[0][col],
+            self.m[1][col],
+            self.m[2][col],
+            self.m[3][col],
+        )
+    }
+
+    pub fn transpose(self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (row, out) in m.iter_mut().enumerate() {
+            for (col, value) in out.iter_mut().enumerate() {
+                *value = self.m[col][row];
+            }
+        }
+        Self::from_rows(m)
+    }
+
+    /// Transforms a point (`w = 1`), performing the perspective divide.
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        let v = *self * Vec4::from_vec3(point, 1.0);
+        v.xyz() / v.w()
+    }
+
+    /// Transforms a direction (`w = 0`), ignoring translation.
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        (*self * Vec4::from_vec3(vector, 0.0)).xyz()
+    }
+
+    /// 2x2 sub-determinants of the top and bottom row pairs, shared by
+    /// `determinant` and `inverse`.
+    fn sub_determinants(&self) -> ([f32; 6], [f32; 6]) {
+        let m = &self.m;
+        let top = [
+            m[0][0] * m[1][1] - m[1][0] * m[0][1],
+            m[0][0] * m[1][2] - m[1][0] * m[0][2],
+            m[0][0] * m[1][3] - m[1][0] * m[0][3],
+            m[0][1] * m[1][2] - m[1][1] * m[0][2],
+            m[0][1] * m[1][3] - m[1][1] * m[0][3],
+            m[0][2] * m[1][3] - m[1][2] * m[0][3],
+        ];
+        let bottom = [
+            m[2][0] * m[3][1] - m[3][0] * m[2][1],
+            m[2][0] * m[3][2] - m[3][0] * m[2][2],
+            m[2][0] * m[3][3] - m[3][0] * m[2][3],
+            m[2][1] * m[3][2] - m[3][1] * m[2][2],
+            m[2][1] * m[3][3] - m[3][1] * m[2][3],
+            m[2][2] * m[3][3] - m[3][2] * m[2][3],
+        ];
+        (top, bottom)
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let (s, c) = self.sub_determinants();
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+    }
+
+    /// The inverse matrix, or `None` if the matrix is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let m = &self.m;
+        let (s, c) = self.sub_determinants();
+        let determinant =
+            s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+
+        let inverse = [
+            [
+                m[1][1] * c[5] - m[1][2] * c[4] + m[1][3] * c[3],
+                -m[0][1] * c[5] + m[0][2] * c[4] - m[0][3] * c[3],
+                m[3][1] * s[5] - m[3][2] * s[4] + m[3][3] * s[3],
+                -m[2][1] * s[5] + m[2][2] * s[4] - m[2][3] * s[3],
+            ],
+            [
+                -m[1][0] * c[5] + m[1][2] * c[2] - m[1][3] * c[1],
+                m[0][0] * c[5] - m[0][2] * c[2] + m[0][3] * c[1],
+                -m[3][0] * s[5] + m[3][2] * s[2] - m[3][3] * s[1],
+                m[2][0] * s[5] - m[2][2] * s[2] + m[2][3] * s[1],
+            ],
+            [
+                m[1][0] * c[4] - m[1][1] * c[2] + m[1][3] * c[0],
+                -m[0][0] * c[4] + m[0][1] * c[2] - m[0][3] * c[0],
+                m[3][0] * s[4] - m[3][1] * s[2] + m[3][3] * s[0],
+                -m[2][0] * s[4] + m[2][1] * s[2] - m[2][3] * s[0],
+            ],
+            [
+                -m[1][0] * c[3] + m[1][1] * c[1] - m[1][2] * c[0],
+                m[0][0] * c[3] - m[0][1] * c[1] + m[0][2] * c[0],
+                -m[3][0] * s[3] + m[3][1] * s[1] - m[3][2] * s[0],
+                m[2][0] * s[3] - m[2][1] * s[1] + m[2][2] * s[0],
+            ],
+        ];
+
+        let inverse_determinant = 1.0 / determinant;
+        Some(Self::from_rows(
+            inverse.map(|row| row.map(|value| value * inverse_determinant)),
+        ))
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (row, out) in m.iter_mut().enumerate() {
+            for (col, value) in out.iter_mut().enumerate() {
+                *value = self.row(row).dot(other.col(col));
+            }
+        }
+        Self::from_rows(m)
+    }
+}
+
+impl Mul<Vec4> for Mat4 {
+    type Output = Vec4;
+
+    fn mul(self, v: Vec4) -> Vec4 {
+        Vec4::new(
+            self.row(0).dot(v),
+            self.row(1).dot(v),
+            self.row(2).dot(v),
+            self.row(3).dot(v),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_identity(m: Mat4, context: &str) {
+        for (row, values) in m.m.iter().enumerate() {
+            for (col, &value) in values.iter().enumerate() {
+                let expected = if row == col { 1.0 } else { 0.0 };
+                assert!(
+                    (value - expected).abs() < 1e-4,
+                    "{context}: m[{row}][{col}] = {value}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_the_matrix() {
+        let matrices = [
+            ("identity", Mat4::IDENTITY),
+            (
+                "affine",
+                Mat4::translation(Vec3::new(3.0, -2.0, 5.0))
+                    * Mat4::rotation(Vec3::new(1.0, 2.0, -0.5), 0.7)
+                    * Mat4::scale(Vec3::new(2.0, 0.5, 3.0)),
+            ),
+            (
+                "view",
+                Mat4::look_at(
+                    Vec3::new(4.0, 3.0, 6.0),
+                    Vec3::new(0.0, 0.5, 0.0),
+                    Vec3::new(0.0, 1.0, 0.0),
+                ),
+            ),
+            (
+                "perspective",
+                Mat4::perspective(1.2, 16.0 / 9.0, 0.1, 100.0),
+            ),
+            (
+                "orthographic",
+                Mat4::orthographic(-4.0, 6.0, -2.0, 3.0, 0.5, 20.0),
+            ),
+            (
+                "projective",
+                Mat4::from_rows([
+                    [2.0, 0.5, 0.0, 1.0],
+                    [0.0, 1.0, -1.0, 0.0],
+                    [0.3, 0.0, 1.0, 2.0],
+                    [0.1, 0.2, 0.5, 1.0],
+                ]),
+            ),
+        ];
+
+        for (name, m) in matrices {
+            let inverse = m
+                .inverse()
+                .unwrap_or_else(|| panic!("{name} is invertible"));
+            assert_identity(m * inverse, name);
+            assert_identity(inverse * m, name);
+        }
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        let flattened = Mat4::scale(Vec3::new(1.0, 0.0, 1.0));
+        let dependent_rows = Mat4::from_rows([
+            [1.0, 2.0, 3.0, 4.0],
+            [2.0, 4.0, 6.0, 8.0],
+            [0.0, 1.0, 0.0, 1.0],
+            [1.0, 0.0, 1.0, 0.0],
+        ]);
+        let not_finite = Mat4::scale(Vec3::new(f32::NAN, 1.0, 1.0));
+
+        assert_eq!(flattened.inverse(), None);
+        assert_eq!(dependent_rows.inverse(), None);
+        assert_eq!(not_finite.inverse(), None);
+    }
+}
@@ -0,0 +1,258 @@
+use crate::vec2::Vec2;
+use crate::vec3::Vec3;
+use anyhow::{Context, Result, anyhow, bail};
+use std::path::Path;
+
+/// One corner of a mesh triangle, indexing into the mesh attribute arrays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshVertex {
+    pub position: usize,
+    pub tex_coord: Option<usize>,
+    pub normal: Option<usize>,
+}
+
+/// Indexed triangle mesh. Polygons are triangulated when loaded, so every
+/// face is a triangle ready for the rasterizer.
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub positions: Vec<Vec3>,
+    pub tex_coords: Vec<Vec2>,
+    pub normals: Vec<Vec3>,
+    pub triangles: Vec<[MeshVertex; 3]>,
+}
+
+impl Mesh {
+    /// Loads a Wavefront OBJ file from disk.
+    pub fn load_obj(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse_obj(&source).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Parses Wavefront OBJ source. Supports `v`, `vt`, `vn` and `f` records
+    /// (including negative indices and n-gons, which are fan-triangulated).
+    /// Grouping, material and smoothing records are ignored.
+    pub fn parse_obj(source: &str) -> Result<Self> {
+        let mut mesh = Self::default();
+
+        for (line_index, line) in source.lines().enumerate() {
+            mesh.parse_obj_line(line)
+                .with_context(|| format!("line {}: {}", line_index + 1, line.trim()))?;
+        }
+
+        Ok(mesh)
+    }
+
+    fn parse_obj_line(&mut self, line: &str) -> Result<()> {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            return Ok(());
+        };
+
+        match keyword {
+            "v" => {
+                let [x, y, z] = parse_floats(tokens, 3)?;
+                self.positions.push(Vec3::new(x, y, z));
+            }
+            "vt" => {
+                // v is optional and defaults to 0; an optional w is parsed
+                // but dropped, since textures are 2D
+                let [u, v, _w] = parse_floats(tokens, 1)?;
+                self.tex_coords.push(Vec2::new(u, v));
+            }
+            "vn" => {
+                let [x, y, z] = parse_floats(tokens, 3)?;
+                self.normals.push(Vec3::new(x, y, z));
+            }
+            "f" => {
+                let corners = tokens
+                    .map(|token| self.parse_face_vertex(token))
+                    .collect::<Result<Vec<_>>>()?;
+                if corners.len() < 3 {
+                    bail!("face needs at least 3 vertices, found {}", corners.len());
+                }
+
+                // Fan triangulation, exact for the convex polygons OBJ exporters emit
+                for i in 1..corners.len() - 1 {
+                    self.triangles
+                        .push([corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Parses `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+    fn parse_face_vertex(&self, token: &str) -> Result<MeshVertex> {
+        let mut parts = token.split('/');
+        let position = parts.next().unwrap_or_default();
+        let tex_coord = parts.next().filter(|part| !part.is_empty());
+        let normal = parts.next().filter(|part| !part.is_empty());
+        if parts.next().is_some() {
+            bail!("malformed face vertex '{}'", token);
+        }
+
+        Ok(MeshVertex {
+            position: resolve_index(position, self.positions.len(), "position")?,
+            tex_coord: tex_coord
+                .map(|index| resolve_index(index, self.tex_coords.len(), "texture coordinate"))
+                .transpose()?,
+            normal: normal
+                .map(|index| resolve_index(index, self.normals.len(), "normal"))
+                .transpose()?,
+        })
+    }
+
+    /// The object-space positions of a triangle's three corners.
+    pub fn triangle_positions(&self, triangle: &[MeshVertex; 3]) -> [Vec3; 3] {
+        triangle.map(|vertex| self.positions[vertex.position])
+    }
+
+    /// Axis-aligned bounds of all positions as `(min, max)`, or `None` for an empty mesh.
+    pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        let first = *self.positions.first()?;
+        Some(
+            self.positions
+                .iter()
+                .fold((first, first), |(min, max), &p| (min.min(p), max.max(p))),
+        )
+    }
+}
+
+/// Parses at least `required` and at most `N` floats; missing optional values are 0.
+fn parse_floats<'a, const N: usize>(
+    tokens: impl Iterator<Item = &'a str>,
+    required: usize,
+) -> Result<[f32; N]> {
+    let mut values = [0.0; N];
+    let mut count = 0;
+
+    for token in tokens.take(N) {
+        values[count] = token
+            .parse()
+            .map_err(|_| anyhow!("invalid number '{}'", token))?;
+        count += 1;
+    }
+
+    if count < required {
+        bail!("expected {} values, found {}", required, count);
+    }
+    Ok(values)
+}
+
+/// Converts a 1-based (or negative, relative-to-end) OBJ index into a 0-based index.
+fn resolve_index(token: &str, count: usize, kind: &str) -> Result<usize> {
+    let index: i64 = token
+        .parse()
+        .map_err(|_| anyhow!("invalid {} index '{}'", kind, token))?;
+
+    let resolved = match index {
+        0 => bail!("{} index 0 is invalid, OBJ indices start at 1", kind),
+        i if i > 0 => i - 1,
+        i => count as i64 + i,
+    };
+
+    if resolved < 0 || resolved >= count as i64 {
+        bail!(
+            "{} index {} out of range ({} defined so far)",
+            kind,
+            index,
+            count
+        );
+    }
+    Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(position: usize, tex_coord: Option<usize>, normal: Option<usize>) -> MeshVertex {
+        MeshVertex {
+            position,
+            tex_coord,
+            normal,
+        }
+    }
+
+    const SQUARE: &str = "
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        vt 0 0
+        vt 1 0
+        vt 1 1 0.5
+        vn 0 0 1
+    ";
+
+    #[test]
+    fn parses_face_vertex_forms() {
+        let source =
+            format!("{SQUARE}\nf 1 2 3\nf 1/1 2/2 3/3\nf 1//1 2//1 3//1\nf 1/1/1 2/2/1 3/3/1");
+        let mesh = Mesh::parse_obj(&source).unwrap();
+
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.tex_coords[2], Vec2::new(1.0, 1.0));
+        assert_eq!(mesh.normals, [Vec3::new(0.0, 0.0, 1.0)]);
+        assert_eq!(
+            mesh.triangles,
+            [
+                [0, 1, 2].map(|i| vertex(i, None, None)),
+                [0, 1, 2].map(|i| vertex(i, Some(i), None)),
+                [0, 1, 2].map(|i| vertex(i, None, Some(0))),
+                [0, 1, 2].map(|i| vertex(i, Some(i), Some(0))),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_negative_indices_relative_to_the_end() {
+        let source = format!("{SQUARE}\nf -4/-3/-1 -3/-2/-1 -2/-1/-1\nv 2 2 2\nf -1 -2 -3");
+        let mesh = Mesh::parse_obj(&source).unwrap();
+
+        assert_eq!(
+            mesh.triangles,
+            [
+                [0, 1, 2].map(|i| vertex(i, Some(i), Some(0))),
+                [4, 3, 2].map(|i| vertex(i, None, None)),
+            ]
+        );
+    }
+
+    #[test]
+    fn fan_triangulates_polygons() {
+        let source = format!("{SQUARE}\nv 0.5 1.5 0\nf 1 2 3 5 4");
+        let mesh = Mesh::parse_obj(&source).unwrap();
+
+        let triangles: Vec<_> = mesh
+            .triangles
+            .iter()
+            .map(|triangle| triangle.map(|corner| corner.position))
+            .collect();
+        assert_eq!(triangles, [[0, 1, 2], [0, 2, 4], [0, 4, 3]]);
+    }
+
+    #[test]
+    fn rejects_invalid_faces() {
+        for face in [
+            "f 1 2 5",
+            "f 1 2 -5",
+            "f 0 1 2",
+            "f 1/4 2 3",
+            "f 1//2 2 3",
+            "f 1 2",
+            "f 1/1/1/1 2 3",
+            "f 1 2 x",
+        ] {
+            let source = format!("{SQUARE}\n{face}");
+            assert!(Mesh::parse_obj(&source).is_err(), "{face}");
+        }
+
+        // Indices only refer to vertices defined before the face
+        assert!(Mesh::parse_obj("v 0 0 0\nv 1 0 0\nf 1 2 3\nv 0 1 0").is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::scene::CameraMode;
+use anyhow::{Context, Result, bail};
+use salmon_rs::blit::{ScaleFilter, ScaleMode};
+use salmon_rs::frame_stats;
+use salmon_rs::image_io::ImageFormat;
+use salmon_rs::software_canvas::MAX_CANVAS_DIMENSION;
+use salmon_rs::timing::{self, DEFAULT_FPS_LIMIT, DEFAULT_TICK_RATE, FpsLimit};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Command-line options for the `salmon_rs` binary.
+pub struct Options {
+    /// Render without a window and write each frame to disk
+    pub headless: bool,
+    /// Number of frames to render in headless mode
+    pub frames: u32,
+    /// Directory headless frames are written to
+    pub out_dir: PathBuf,
+    /// File format of headless frames
+    pub format: ImageFormat,
+    /// Logical resolution of the canvas
+    pub canvas_size: (u32, u32),
+    /// How the canvas is fitted into the window
+    pub scale_mode: ScaleMode,
+    /// Filter used when scaling the canvas to the window
+    pub scale_filter: ScaleFilter,
+    /// Fraction of the window size the canvas follows, instead of `canvas_size`
+    pub follow_window: Option<f32>,
+    /// Render time per frame the dynamic resolution controller aims for
+    pub target_frame_time: Option<Duration>,
+    /// Lowest fraction of the window size dynamic resolution may drop to
+    pub min_scale: f32,
+    /// Frame rate the window is paced to
+    pub fps_limit: FpsLimit,
+    /// CSV file recent frame durations are written to on exit
+    pub stats_path: Option<PathBuf>,
+    /// Number of recent frames kept for statistics
+    pub stats_frames: usize,
+    /// Fixed simulation steps per second
+    pub tick_rate: f32,
+    /// Action binding config overriding the default bindings
+    pub bindings_path: Option<PathBuf>,
+    /// OBJ mesh to view instead of the built-in 2D frame
+    pub mesh_path: Option<PathBuf>,
+    /// Camera controller the mesh viewer starts with
+    pub camera_mode: CameraMode,
+    /// Image edited in paint mode, loaded if it exists and written on save
+    pub paint_path: Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            headless: false,
+            frames: 1,
+            out_dir: PathBuf::from("frames"),
+            format: ImageFormat::Png,
+            canvas_size: (64, 64),
+            scale_mode: ScaleMode::Stretch,
+            scale_filter: ScaleFilter::Nearest,
+            follow_window: None,
+            target_frame_time: None,
+            min_scale: 0.25,
+            fps_limit: DEFAULT_FPS_LIMIT,
+            stats_path: None,
+            stats_frames: frame_stats::DEFAULT_CAPACITY,
+            tick_rate: DEFAULT_TICK_RATE,
+            bindings_path: None,
+            mesh_path: None,
+            camera_mode: CameraMode::default(),
+            paint_path: None,
+        }
+    }
+}
+
+impl Options {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .with_context(|| format!("Missing value for {}", name))
+            };
+
+            match arg.as_str() {
+                "--headless" => options.headless = true,
+                "--frames" => {
+                    let frames = value("--frames")?;
+                    options.frames = frames
+                        .parse()
+                        .with_context(|| format!("Invalid frame count: {}", frames))?;
+                }
+                "--out" => options.out_dir = PathBuf::from(value("--out")?),
+                "--format" => {
+                    let format = value("--format")?;
+                    options.format = ImageFormat::from_extension(&format)
+                        .with_context(|| format!("Unsupported image format: {}", format))?;
+                }
+                "--size" => options.canvas_size = parse_size(&value("--size")?)?,
+                "--scale" => {
+                    let mode = value("--scale")?;
+                    options.scale_mode = ScaleMode::from_name(&mode)
+                        .with_context(|| format!("Unknown scale mode: {}", mode))?;
+                }
+                "--filter" => {
+                    let filter = value("--filter")?;
+                    options.scale_filter = ScaleFilter::from_name(&filter)
+                        .with_context(|| format!("Unknown scale filter: {}", filter))?;
+                }
+                "--follow" => {
+                    let fraction = value("--follow")?;
+                    match fraction.parse::<f32>() {
+                        Ok(fraction) if fraction > 0.0 && fraction <= 1.0 => {
+                            options.follow_window = Some(fraction);
+                        }
+                        _ => bail!(
+                            "Invalid window fraction: {} (expected 0 < F <= 1)",
+                            fraction
+                        ),
+                    }
+                }
+                "--dynamic" => {
+                    let millis = value("--dynamic")?;
+                    let frame_time = millis
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|millis| *millis > 0.0 && millis.is_finite())
+                        .and_then(|millis| Duration::try_from_secs_f32(millis / 1000.0).ok())
+                        .filter(|frame_time| !frame_time.is_zero());
+                    match frame_time {
+                        Some(frame_time) => options.target_frame_time = Some(frame_time),
+                        None => bail!("Invalid frame time: {}", millis),
+                    }
+                }
+                "--min-scale" => {
+                    let scale = value("--min-scale")?;
+                    match scale.parse::<f32>() {
+                        Ok(scale) if scale > 0.0 && scale <= 1.0 => options.min_scale = scale,
+                        _ => bail!("Invalid minimum scale: {} (expected 0 < F <= 1)", scale),
+                    }
+                }
+                "--fps" => {
+                    let limit = value("--fps")?;
+                    options.fps_limit = FpsLimit::from_name(&limit)
+                        .with_context(|| format!("Invalid frame rate limit: {}", limit))?;
+                }
+                "--stats" => options.stats_path = Some(PathBuf::from(value("--stats")?)),
+                "--stats-frames" => {
+                    let frames = value("--stats-frames")?;
+                    match frames.parse() {
+                        Ok(frames) if frames > 0 => options.stats_frames = frames,
+                        _ => bail!("Invalid frame count: {}", frames),
+                    }
+                }
+                "--tick-rate" => {
+                    let rate = value("--tick-rate")?;
+                    match rate.parse::<f32>() {
+                        Ok(rate) if timing::tick_period(rate).is_some() => options.tick_rate = rate,
+                        _ => bail!("Invalid tick rate: {}", rate),
+                    }
+                }
+                "--bindings" => options.bindings_path = Some(PathBuf::from(value("--bindings")?)),
+                "--mesh" => options.mesh_path = Some(PathBuf::from(value("--mesh")?)),
+                "--camera" => {
+                    let mode = value("--camera")?;
+                    options.camera_mode = CameraMode::from_name(&mode)
+                        .with_context(|| format!("Unknown camera mode: {}", mode))?;
+                }
+                "--paint" => options.paint_path = Some(PathBuf::from(value("--paint")?)),
+                "--help" | "-h" => {
+                    println!("{}", USAGE);
+                    std::process::exit(0);
+                }
+                _ => bail!("Unknown argument: {}\n\n{}", arg, USAGE),
+            }
+        }
+
+        if options.mesh_path.is_some() && options.paint_path.is_some() {
+            bail!("--mesh and --paint can't be used together");
+        }
+        // Resizing the canvas to the window would wipe the painting
+        if options.paint_path.is_some() && options.follow_window.is_some() {
+            bail!("--follow and --paint can't be used together");
+        }
+        if options.paint_path.is_some() && options.target_frame_time.is_some() {
+            bail!("--dynamic and --paint can't be used together");
+        }
+        Ok(options)
+    }
+}
+
+/// Parses a `WIDTHxHEIGHT` size such as `320x180`, each side at most
+/// `MAX_CANVAS_DIMENSION`.
+fn parse_size(size: &str) -> Result<(u32, u32)> {
+    let parsed = size
+        .split_once('x')
+        .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+    let valid = 1..=MAX_CANVAS_DIMENSION;
+    match parsed {
+        Some((width, height)) if valid.contains(&width) && valid.contains(&height) => {
+            Ok((width, height))
+        }
+        _ => bail!(
+            "Invalid size: {} (expected WIDTHxHEIGHT, each 1 to {})",
+            size,
+            MAX_CANVAS_DIMENSION
+        ),
+    }
+}
+
+const USAGE: &str = "\
+Usage: salmon_rs [OPTIONS]
+
+Options:
+  --headless         Render without a window and write frames to disk
+  --frames <N>       Number of frames to render in headless mode [default: 1]
+  --out <DIR>        Output directory for headless frames [default: frames]
+  --format <FORMAT>  Frame image format: png, ppm or tga [default: png]
+  --size <WxH>       Canvas resolution, each side up to 16384 [default: 64x64]
+  --scale <MODE>     Canvas scaling: stretch, integer or fit [default: stretch]
+  --filter <FILTER>  Scaling filter: nearest or bilinear [default: nearest]
+  --follow <F>       Resize the canvas to F times the window size, e.g. 1 or 0.5
+  --dynamic <MS>     Scale the canvas resolution to render frames in MS
+                     milliseconds, up to the --follow fraction (1 if unset)
+  --min-scale <F>    Lowest fraction of the window size --dynamic may use
+                     [default: 0.25]
+  --fps <LIMIT>      Frame rate limit: a number, unlimited or monitor
+                     [default: 320]
+  --stats <FILE>     Write recent frame durations to a CSV file on exit
+  --stats-frames <N> Number of recent frames kept for statistics
+                     [default: 16384]
+  --tick-rate <HZ>   Fixed simulation steps per second [default: 60]
+  --bindings <FILE>  Action bindings, one 'action = Input, ...' per line
+  --mesh <FILE>      View an OBJ mesh with an interactive camera
+  --camera <MODE>    Initial camera controller: orbit or fly [default: orbit]
+  --paint <FILE>     Paint pixels on the canvas, loading FILE if it exists and
+                     writing it on save
+  -h, --help         Print this help";
@@ -0,0 +1,353 @@
+//! Sprite editing on the canvas: freehand pencil, flood fill, line and
+//! rectangle tools, an eyedropper, and undo/redo of every edit.
+
+use crate::input::Input;
+use crate::line;
+use crate::software_canvas::SoftwareCanvas;
+use crate::vec4::Vec4;
+use anyhow::Result;
+use std::path::Path;
+
+/// Undo steps kept before the oldest ones are dropped.
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// Colors selected by the `color_1` to `color_8` actions.
+pub const PALETTE: [Vec4; 8] = [
+    Vec4::new(0.0, 0.0, 0.0, 1.0),
+    Vec4::new(1.0, 1.0, 1.0, 1.0),
+    Vec4::new(1.0, 0.0, 0.0, 1.0),
+    Vec4::new(0.0, 1.0, 0.0, 1.0),
+    Vec4::new(0.0, 0.0, 1.0, 1.0),
+    Vec4::new(1.0, 1.0, 0.0, 1.0),
+    Vec4::new(1.0, 0.0, 1.0, 1.0),
+    Vec4::new(0.0, 0.0, 0.0, 0.0),
+];
+
+const COLOR_ACTIONS: [&str; PALETTE.len()] = [
+    "color_1", "color_2", "color_3", "color_4", "color_5", "color_6", "color_7", "color_8",
+];
+
+/// What a press of the `paint` action does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Tool {
+    /// Freehand strokes while the button is held
+    #[default]
+    Pencil,
+    /// Paint the region of same-colored pixels under the cursor
+    Fill,
+    /// Straight line from where the drag started
+    Line,
+    /// Rectangle outline spanning the drag
+    Rect,
+    /// Select the color under the cursor
+    Eyedropper,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 5] = [
+        Self::Pencil,
+        Self::Fill,
+        Self::Line,
+        Self::Rect,
+        Self::Eyedropper,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "pencil" => Some(Self::Pencil),
+            "fill" => Some(Self::Fill),
+            "line" => Some(Self::Line),
+            "rect" => Some(Self::Rect),
+            "eyedropper" => Some(Self::Eyedropper),
+            _ => None,
+        }
+    }
+
+    /// Action that selects this tool.
+    pub fn action(self) -> &'static str {
+        match self {
+            Self::Pencil => "tool_pencil",
+            Self::Fill => "tool_fill",
+            Self::Line => "tool_line",
+            Self::Rect => "tool_rect",
+            Self::Eyedropper => "tool_eyedropper",
+        }
+    }
+}
+
+/// Canvas contents saved for undo.
+#[derive(Debug, Clone, PartialEq)]
+struct Snapshot {
+    size: (u32, u32),
+    pixels: Vec<u32>,
+}
+
+impl Snapshot {
+    fn capture(canvas: &SoftwareCanvas) -> Self {
+        Self {
+            size: (canvas.width(), canvas.height()),
+            pixels: canvas.pixels().to_vec(),
+        }
+    }
+
+    fn restore(&self, canvas: &mut SoftwareCanvas) {
+        if (canvas.width(), canvas.height()) != self.size {
+            canvas.set_canvas_size(self.size.0, self.size.1);
+        }
+        canvas.pixels_mut().copy_from_slice(&self.pixels);
+    }
+}
+
+/// A press of the `paint` action that hasn't been released yet.
+#[derive(Debug, Clone)]
+struct Stroke {
+    tool: Tool,
+    start: (i32, i32),
+    last: (i32, i32),
+    /// Canvas before the stroke, for undo and for redrawing shape previews
+    before: Snapshot,
+}
+
+/// Edits the canvas from the `paint` action and cursor, and keeps the
+/// history. Pixels are written with the canvas blend mode.
+///
+/// Tools are selected with `tool_pencil`, `tool_fill`, `tool_line`,
+/// `tool_rect` and `tool_eyedropper`, palette colors with `color_1` to
+/// `color_8`, and `undo` and `redo` step through the history.
+#[derive(Debug, Clone)]
+pub struct PaintEditor {
+    tool: Tool,
+    color: Vec4,
+    stroke: Option<Stroke>,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    history_limit: usize,
+}
+
+impl Default for PaintEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PaintEditor {
+    pub fn new() -> Self {
+        Self {
+            tool: Tool::default(),
+            color: PALETTE[1],
+            stroke: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            history_limit: DEFAULT_HISTORY_LIMIT,
+        }
+    }
+
+    pub fn tool(&self) -> Tool {
+        self.tool
+    }
+
+    /// Selects the tool for the next stroke; a stroke in progress finishes
+    /// with the tool it started with.
+    pub fn set_tool(&mut self, tool: Tool) {
+        self.tool = tool;
+    }
+
+    pub fn color(&self) -> Vec4 {
+        self.color
+    }
+
+    pub fn set_color(&mut self, color: Vec4) {
+        self.color = color;
+    }
+
+    pub fn history_limit(&self) -> usize {
+        self.history_limit
+    }
+
+    /// Sets how many undo steps are kept, dropping the oldest beyond it.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_limit = limit;
+        self.trim_history();
+    }
+
+    /// Whether a stroke is in progress.
+    pub fn is_painting(&self) -> bool {
+        self.stroke.is_some()
+    }
+
+    /// Applies this frame's input to the editor and `canvas`.
+    pub fn update(&mut self, input: &Input, canvas: &mut SoftwareCanvas) {
+        for tool in Tool::ALL {
+            if input.was_action_pressed(tool.action()) {
+                self.set_tool(tool);
+            }
+        }
+        for (action, &color) in COLOR_ACTIONS.iter().zip(&PALETTE) {
+            if input.was_action_pressed(action) {
+                self.color = color;
+            }
+        }
+        if !self.is_painting() {
+            if input.was_action_pressed("undo") {
+                self.undo(canvas);
+            }
+            if input.was_action_pressed("redo") {
+                self.redo(canvas);
+            }
+        }
+
+        let position = input
+            .cursor_canvas_position(canvas)
+            .map(|position| (position.x.floor() as i32, position.y.floor() as i32));
+        if let Some(position) = position {
+            if self.is_painting() {
+                self.drag(canvas, position);
+            } else if input.was_action_pressed("paint") {
+                self.begin(canvas, position);
+            }
+        }
+        // A click can press and release within one frame
+        if self.is_painting() && !input.is_action_down("paint") {
+            self.end(canvas);
+        }
+    }
+
+    /// Starts a stroke at canvas pixel `position`.
+    pub fn begin(&mut self, canvas: &mut SoftwareCanvas, position: (i32, i32)) {
+        self.stroke = Some(Stroke {
+            tool: self.tool,
+            start: position,
+            last: position,
+            before: Snapshot::capture(canvas),
+        });
+        self.apply(canvas, position);
+    }
+
+    /// Continues the stroke in progress to canvas pixel `position`. Fills
+    /// only happen where the stroke started.
+    pub fn drag(&mut self, canvas: &mut SoftwareCanvas, position: (i32, i32)) {
+        if self
+            .stroke
+            .as_ref()
+            .is_some_and(|stroke| stroke.tool != Tool::Fill && stroke.last != position)
+        {
+            self.apply(canvas, position);
+        }
+    }
+
+    /// Finishes the stroke in progress, recording it for undo if it changed
+    /// the canvas.
+    pub fn end(&mut self, canvas: &SoftwareCanvas) {
+        if let Some(stroke) = self.stroke.take()
+            && stroke.before != Snapshot::capture(canvas)
+        {
+            self.push_undo(stroke.before);
+        }
+    }
+
+    fn apply(&mut self, canvas: &mut SoftwareCanvas, position: (i32, i32)) {
+        let Some(stroke) = &mut self.stroke else {
+            return;
+        };
+        let (x, y) = position;
+        let color = self.color;
+
+        match stroke.tool {
+            Tool::Pencil => {
+                // Connect to the previous position so fast drags leave no
+                // gaps, skipping that pixel so blended strokes don't cover it twice
+                let last = stroke.last;
+                let size = (canvas.width(), canvas.height());
+                let first = last == position;
+                line::bresenham(last, position, size, |px, py| {
+                    if first || (px as i32, py as i32) != last {
+                        canvas.set_pixel(px, py, color);
+                    }
+                });
+            }
+            Tool::Fill => {
+                if let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) {
+                    canvas.flood_fill(x, y, color);
+                }
+            }
+            Tool::Line => {
+                stroke.before.restore(canvas);
+                let (start_x, start_y) = stroke.start;
+                canvas.draw_line(start_x, start_y, x, y, color);
+            }
+            Tool::Rect => {
+                stroke.before.restore(canvas);
+                let (start_x, start_y) = stroke.start;
+                canvas.draw_rect(
+                    start_x.min(x),
+                    start_y.min(y),
+                    start_x.abs_diff(x) + 1,
+                    start_y.abs_diff(y) + 1,
+                    color,
+                );
+            }
+            Tool::Eyedropper => {
+                if let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y))
+                    && let Some(picked) = canvas.get_pixel(x, y)
+                {
+                    self.color = picked;
+                }
+            }
+        }
+        stroke.last = position;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Reverts the last edit. Returns `false` if there is nothing to undo.
+    pub fn undo(&mut self, canvas: &mut SoftwareCanvas) -> bool {
+        let Some(snapshot) = self.undo.pop() else {
+            return false;
+        };
+        self.redo.push(Snapshot::capture(canvas));
+        snapshot.restore(canvas);
+        true
+    }
+
+    /// Reapplies the last undone edit. Returns `false` if there is nothing to redo.
+    pub fn redo(&mut self, canvas: &mut SoftwareCanvas) -> bool {
+        let Some(snapshot) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push(Snapshot::capture(canvas));
+        snapshot.restore(canvas);
+        true
+    }
+
+    /// Writes the canvas to an image file; see `SoftwareCanvas::save_image`.
+    pub fn save(&self, canvas: &SoftwareCanvas, path: &Path) -> Result<()> {
+        canvas.save_image(path)
+    }
+
+    /// Replaces the canvas with an image file as an undoable edit; see
+    /// `SoftwareCanvas::load_image`.
+    pub fn load(&mut self, canvas: &mut SoftwareCanvas, path: &Path) -> Result<()> {
+        let before = Snapshot::capture(canvas);
+        canvas.load_image(path)?;
+        self.stroke = None;
+        self.push_undo(before);
+        Ok(())
+    }
+
+    fn push_undo(&mut self, snapshot: Snapshot) {
+        self.undo.push(snapshot);
+        self.redo.clear();
+        self.trim_history();
+    }
+
+    fn trim_history(&mut self) {
+        let excess = self.undo.len().saturating_sub(self.history_limit);
+        self.undo.drain(..excess);
+    }
+}
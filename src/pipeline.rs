@@ -0,0 +1,334 @@
+//! Shading stage of `SoftwareCanvas::draw_mesh`.
+//!
+//! Triangles arrive here already clipped and projected. They are either shaded
+//! one after another over the whole canvas, or binned into square tiles and
+//! shaded by the canvas's worker pool, each worker owning a band of tile rows
+//! at a time.
+//! Within a tile triangles are shaded in submission order and every pixel goes
+//! through the exact same arithmetic, so both paths produce identical output.
+
+use crate::blend::BlendMode;
+use crate::rasterizer::{
+    PixelRect, ScreenVertex, rasterize_screen_triangle, screen_weight_gradients,
+};
+use crate::shader::{Fragment, Interpolator, Shader, Varyings};
+use crate::software_canvas::{DepthFunc, PickId};
+use crate::worker_pool::WorkerPool;
+use std::sync::Mutex;
+
+/// Edge length, in pixels, of the square tiles triangles are binned into.
+pub(crate) const TILE_SIZE: u32 = 32;
+
+/// A projected triangle ready to be shaded.
+pub(crate) struct SetupTriangle<V> {
+    pub screen: [ScreenVertex; 3],
+    pub varyings: [V; 3],
+    /// Index of the mesh triangle this was clipped from, for picking
+    pub primitive: u32,
+    /// Pixels the triangle can touch, clamped to the canvas
+    pub bounds: PixelRect,
+}
+
+impl<V> SetupTriangle<V> {
+    /// Returns `None` when the triangle's bounding box misses the canvas.
+    pub fn new(
+        screen: [ScreenVertex; 3],
+        varyings: [V; 3],
+        primitive: u32,
+        canvas_size: (u32, u32),
+    ) -> Option<Self> {
+        let min_x = screen[0].x.min(screen[1].x).min(screen[2].x);
+        let max_x = screen[0].x.max(screen[1].x).max(screen[2].x);
+        let min_y = screen[0].y.min(screen[1].y).min(screen[2].y);
+        let max_y = screen[0].y.max(screen[1].y).max(screen[2].y);
+
+        let clamp = |value: f32, size: u32| value.clamp(0.0, size as f32) as u32;
+        let bounds = PixelRect::new(
+            clamp(min_x.floor(), canvas_size.0),
+            clamp(min_y.floor(), canvas_size.1),
+            clamp(max_x.ceil() + 1.0, canvas_size.0),
+            clamp(max_y.ceil() + 1.0, canvas_size.1),
+        );
+        (bounds.x0 < bounds.x1 && bounds.y0 < bounds.y1).then_some(Self {
+            screen,
+            varyings,
+            primitive,
+            bounds,
+        })
+    }
+}
+
+/// Fixed-function state captured at the start of a draw.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RasterState {
+    pub canvas_size: (u32, u32),
+    pub depth_func: DepthFunc,
+    pub depth_write: bool,
+    pub blend_mode: BlendMode,
+    /// Object ID recorded in the pick buffer, if there is one
+    pub pick_object: u32,
+}
+
+/// Mutable view of the framebuffer rows starting at `y0`.
+struct TargetRows<'a> {
+    y0: u32,
+    color: &'a mut [u32],
+    depth: &'a mut [f32],
+    pick: Option<&'a mut [Option<PickId>]>,
+}
+
+fn intersect(a: PixelRect, b: PixelRect) -> Option<PixelRect> {
+    let rect = PixelRect::new(
+        a.x0.max(b.x0),
+        a.y0.max(b.y0),
+        a.x1.min(b.x1),
+        a.y1.min(b.y1),
+    );
+    (rect.x0 < rect.x1 && rect.y0 < rect.y1).then_some(rect)
+}
+
+/// Shades the part of `triangle` inside `bounds`, which must lie within `target`.
+fn shade_triangle<S: Shader>(
+    triangle: &SetupTriangle<S::Varyings>,
+    shader: &S,
+    state: &RasterState,
+    bounds: PixelRect,
+    target: &mut TargetRows,
+) {
+    let width = state.canvas_size.0;
+    let (screen_dx, screen_dy) = screen_weight_gradients(&triangle.screen);
+    let interpolator = Interpolator {
+        corners: [
+            &triangle.varyings[0],
+            &triangle.varyings[1],
+            &triangle.varyings[2],
+        ],
+        inverse_w: triangle.screen.map(|vertex| vertex.inverse_w),
+        screen_dx,
+        screen_dy,
+    };
+
+    rasterize_screen_triangle(triangle.screen, bounds, |x, y, depth, weights| {
+        let index = (y - target.y0) as usize * width as usize + x as usize;
+        if !state.depth_func.passes(depth, target.depth[index]) {
+            return;
+        }
+
+        let fragment = Fragment {
+            x,
+            y,
+            depth,
+            varyings: S::Varyings::interpolate(interpolator.corners, &weights),
+            weights,
+            interpolator,
+        };
+        if let Some(color) = shader.fragment(&fragment) {
+            state.blend_mode.write(&mut target.color[index], color);
+            if state.depth_write {
+                target.depth[index] = depth;
+            }
+            if let Some(pick) = target.pick.as_deref_mut() {
+                pick[index] = Some(PickId {
+                    object: state.pick_object,
+                    primitive: triangle.primitive,
+                });
+            }
+        }
+    });
+}
+
+/// Shades `triangles` in order into the color, depth and optional pick
+/// buffers, spreading the work over `pool` when it has more than one thread.
+pub(crate) fn shade_triangles<S: Shader>(
+    triangles: &[SetupTriangle<S::Varyings>],
+    shader: &S,
+    state: RasterState,
+    color: &mut [u32],
+    depth: &mut [f32],
+    pick: Option<&mut [Option<PickId>]>,
+    pool: &mut WorkerPool,
+) {
+    let (width, height) = state.canvas_size;
+    let tiles_x = width.div_ceil(TILE_SIZE);
+    let tiles_y = height.div_ceil(TILE_SIZE);
+
+    if pool.threads() <= 1 || tiles_y <= 1 || triangles.is_empty() {
+        let mut target = TargetRows {
+            y0: 0,
+            color,
+            depth,
+            pick,
+        };
+        for triangle in triangles {
+            shade_triangle(triangle, shader, &state, triangle.bounds, &mut target);
+        }
+        return;
+    }
+
+    // Bin triangle indices by the tiles their bounding boxes overlap
+    let mut bins: Vec<Vec<u32>> = vec![Vec::new(); tiles_x as usize * tiles_y as usize];
+    for (index, triangle) in triangles.iter().enumerate() {
+        let bounds = triangle.bounds;
+        for tile_y in bounds.y0 / TILE_SIZE..bounds.y1.div_ceil(TILE_SIZE) {
+            for tile_x in bounds.x0 / TILE_SIZE..bounds.x1.div_ceil(TILE_SIZE) {
+                bins[tile_y as usize * tiles_x as usize + tile_x as usize].push(index as u32);
+            }
+        }
+    }
+
+    // Each band of tile rows is a disjoint slice of the buffers, handed out
+    // to whichever worker asks next
+    let band_len = width as usize * TILE_SIZE as usize;
+    let mut pick_bands = pick.map(|pick| pick.chunks_mut(band_len));
+    let bands = color
+        .chunks_mut(band_len)
+        .zip(depth.chunks_mut(band_len))
+        .map(move |(color, depth)| (color, depth, pick_bands.as_mut().and_then(Iterator::next)))
+        .enumerate()
+        .filter(|(tile_y, _)| {
+            let row = &bins[tile_y * tiles_x as usize..(tile_y + 1) * tiles_x as usize];
+            row.iter().any(|bin| !bin.is_empty())
+        });
+    let queue = Mutex::new(bands);
+    let bins = &bins;
+
+    pool.run(&|| {
+        loop {
+            let next = queue.lock().expect("tile queue poisoned").next();
+            let Some((tile_y, (color, depth, pick))) = next else {
+                break;
+            };
+            let tile_y = tile_y as u32;
+            let mut target = TargetRows {
+                y0: tile_y * TILE_SIZE,
+                color,
+                depth,
+                pick,
+            };
+
+            for tile_x in 0..tiles_x {
+                let tile = PixelRect::new(
+                    tile_x * TILE_SIZE,
+                    tile_y * TILE_SIZE,
+                    ((tile_x + 1) * TILE_SIZE).min(width),
+                    ((tile_y + 1) * TILE_SIZE).min(height),
+                );
+                for &index in &bins[tile_y as usize * tiles_x as usize + tile_x as usize] {
+                    let triangle = &triangles[index as usize];
+                    if let Some(bounds) = intersect(tile, triangle.bounds) {
+                        shade_triangle(triangle, shader, &state, bounds, &mut target);
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shader::{VertexInput, VertexOutput};
+    use crate::vec4::Vec4;
+
+    /// Shades with the interpolated corner colors.
+    struct ColorShader;
+
+    impl Shader for ColorShader {
+        type Varyings = Vec4;
+
+        fn vertex(&self, _input: &VertexInput) -> VertexOutput<Vec4> {
+            unreachable!("triangles are set up directly")
+        }
+
+        fn fragment(&self, fragment: &Fragment<Vec4>) -> Option<Vec4> {
+            Some(fragment.varyings)
+        }
+    }
+
+    /// Overlapping triangles of every size scattered over `canvas_size`, from
+    /// a fixed-seed generator so runs are reproducible.
+    fn scattered_triangles(canvas_size: (u32, u32)) -> Vec<SetupTriangle<Vec4>> {
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut random = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed >> 40) as f32 / (1u64 << 24) as f32
+        };
+
+        let (width, height) = (canvas_size.0 as f32, canvas_size.1 as f32);
+        (0..400)
+            .filter_map(|primitive| {
+                let size = 4.0 + random() * 120.0;
+                let (cx, cy) = (random() * width, random() * height);
+                let screen = std::array::from_fn(|_| ScreenVertex {
+                    x: cx + (random() - 0.5) * size,
+                    y: cy + (random() - 0.5) * size,
+                    depth: random(),
+                    inverse_w: 0.5 + random(),
+                });
+                let varyings =
+                    std::array::from_fn(|_| Vec4::new(random(), random(), random(), random()));
+                SetupTriangle::new(screen, varyings, primitive, canvas_size)
+            })
+            .collect()
+    }
+
+    type Buffers = (Vec<u32>, Vec<f32>, Vec<Option<PickId>>);
+
+    fn render(state: RasterState, triangles: &[SetupTriangle<Vec4>], threads: usize) -> Buffers {
+        let len = state.canvas_size.0 as usize * state.canvas_size.1 as usize;
+        let mut color = vec![0xff00_0000; len];
+        let mut depth = vec![1.0; len];
+        let mut pick = vec![None; len];
+        let mut pool = WorkerPool::new(threads);
+        for _ in 0..2 {
+            shade_triangles(
+                triangles,
+                &ColorShader,
+                state,
+                &mut color,
+                &mut depth,
+                Some(&mut pick),
+                &mut pool,
+            );
+        }
+        (color, depth, pick)
+    }
+
+    #[test]
+    fn threaded_shading_matches_single_threaded() {
+        let canvas_size = (333, 250);
+        let triangles = scattered_triangles(canvas_size);
+        assert!(triangles.len() > 300);
+
+        for (depth_func, blend_mode) in [
+            (DepthFunc::Less, BlendMode::Replace),
+            (DepthFunc::LessEqual, BlendMode::Alpha),
+            (DepthFunc::Always, BlendMode::Additive),
+        ] {
+            let state = RasterState {
+                canvas_size,
+                depth_func,
+                depth_write: true,
+                blend_mode,
+                pick_object: 7,
+            };
+            let expected = render(state, &triangles, 1);
+            let covered = expected.2.iter().filter(|id| id.is_some()).count();
+            assert!(covered > expected.2.len() / 2);
+            for threads in [2, 3, 8] {
+                let actual = render(state, &triangles, threads);
+                assert!(
+                    expected.0 == actual.0,
+                    "{threads} threads, {blend_mode:?}: colors differ"
+                );
+                assert!(
+                    expected.1 == actual.1,
+                    "{threads} threads, {depth_func:?}: depths differ"
+                );
+                assert!(expected.2 == actual.2, "{threads} threads: pick IDs differ");
+            }
+        }
+    }
+}
@@ -0,0 +1,367 @@
+//! Edge-function triangle rasterization shared by the canvas draw calls.
+//!
+//! Vertices are snapped to a fixed-point sub-pixel grid so that edge functions
+//! are evaluated exactly. Together with the top-left fill rule this guarantees
+//! that triangles sharing an edge never both cover, or both miss, a pixel.
+
+use crate::shader::Weights;
+use std::cmp::Ordering;
+
+/// Fractional bits of the sub-pixel grid vertices are snapped to.
+const SUBPIXEL_BITS: u32 = 8;
+const SUBPIXEL_ONE: i64 = 1 << SUBPIXEL_BITS;
+const SUBPIXEL_HALF: i64 = SUBPIXEL_ONE / 2;
+
+/// Largest coordinate magnitude (in pixels) the fixed-point edge functions can
+/// represent without overflowing.
+const MAX_COORDINATE: f32 = (1 << 21) as f32;
+
+/// Half-open pixel rectangle `[x0, x1) x [y0, y1)` a triangle is rasterized into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PixelRect {
+    pub x0: u32,
+    pub y0: u32,
+    pub x1: u32,
+    pub y1: u32,
+}
+
+impl PixelRect {
+    pub fn new(x0: u32, y0: u32, x1: u32, y1: u32) -> Self {
+        Self { x0, y0, x1, y1 }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Edge {
+    /// Change of the edge function per pixel step in x and y
+    step_x: i64,
+    step_y: i64,
+    /// Whether samples exactly on this edge belong to the triangle
+    top_left: bool,
+}
+
+impl Edge {
+    /// Edge from `a` to `b` of a counter-clockwise (y-up) triangle.
+    fn new(a: (i64, i64), b: (i64, i64)) -> Self {
+        let dx = b.0 - a.0;
+        let dy = b.1 - a.1;
+        // With y pointing up and CCW winding the interior is left of each edge:
+        // a top edge runs right-to-left, a left edge runs downwards.
+        let top_left = (dy == 0 && dx < 0) || dy < 0;
+        Self {
+            step_x: -dy * SUBPIXEL_ONE,
+            step_y: dx * SUBPIXEL_ONE,
+            top_left,
+        }
+    }
+
+    fn covers(&self, value: i64) -> bool {
+        value > 0 || (value == 0 && self.top_left)
+    }
+}
+
+/// Twice the signed area of `a, b, p`; positive when `p` lies left of `a -> b`.
+fn edge_function(a: (i64, i64), b: (i64, i64), p: (i64, i64)) -> i64 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+fn snap(vertex: (f32, f32)) -> Option<(i64, i64)> {
+    let in_range = |v: f32| v.is_finite() && v.abs() <= MAX_COORDINATE;
+    if !in_range(vertex.0) || !in_range(vertex.1) {
+        return None;
+    }
+    Some((
+        (vertex.0 * SUBPIXEL_ONE as f32).round() as i64,
+        (vertex.1 * SUBPIXEL_ONE as f32).round() as i64,
+    ))
+}
+
+/// Snapped, counter-clockwise triangle with its edge functions evaluated at
+/// the first pixel center of its clamped bounding box.
+struct TriangleSetup {
+    edges: [Edge; 3],
+    /// Edge function values at `(start_x, start_y)`, opposite each vertex
+    origin: [i64; 3],
+    start_x: i64,
+    end_x: i64,
+    start_y: i64,
+    end_y: i64,
+    area: i64,
+    /// Whether the caller's last two vertices were swapped to make it CCW
+    swapped: bool,
+}
+
+impl TriangleSetup {
+    fn new(vertices: [(f32, f32); 3], bounds: PixelRect) -> Option<Self> {
+        let (a, b, c) = (snap(vertices[0])?, snap(vertices[1])?, snap(vertices[2])?);
+
+        // Normalize to counter-clockwise winding, remembering how to map the
+        // weights back to the caller's vertex order.
+        let area = edge_function(a, b, c);
+        if area == 0 {
+            return None;
+        }
+        let (b, c, swapped) = if area < 0 {
+            (c, b, true)
+        } else {
+            (b, c, false)
+        };
+
+        // Bounding box of the triangle, clamped to the target rectangle
+        let min_x = a.0.min(b.0).min(c.0);
+        let max_x = a.0.max(b.0).max(c.0);
+        let min_y = a.1.min(b.1).min(c.1);
+        let max_y = a.1.max(b.1).max(c.1);
+
+        let first_pixel = |v: i64| (v - SUBPIXEL_HALF).div_euclid(SUBPIXEL_ONE);
+        let last_pixel = |v: i64| (v - SUBPIXEL_HALF).div_euclid(SUBPIXEL_ONE) + 1;
+        let start_x = first_pixel(min_x).max(bounds.x0 as i64);
+        let end_x = last_pixel(max_x).min(bounds.x1 as i64 - 1);
+        let start_y = first_pixel(min_y).max(bounds.y0 as i64);
+        let end_y = last_pixel(max_y).min(bounds.y1 as i64 - 1);
+        if start_x > end_x || start_y > end_y {
+            return None;
+        }
+
+        // Edge function values at the center of the first pixel
+        let origin = (
+            start_x * SUBPIXEL_ONE + SUBPIXEL_HALF,
+            start_y * SUBPIXEL_ONE + SUBPIXEL_HALF,
+        );
+        Some(Self {
+            edges: [Edge::new(b, c), Edge::new(c, a), Edge::new(a, b)],
+            origin: [
+                edge_function(b, c, origin),
+                edge_function(c, a, origin),
+                edge_function(a, b, origin),
+            ],
+            start_x,
+            end_x,
+            start_y,
+            end_y,
+            area: area.abs(),
+            swapped,
+        })
+    }
+}
+
+/// Calls `visit(x, y, weights)` for every pixel in `bounds` whose center is
+/// covered by the triangle, where `weights` are the barycentric coordinates of
+/// the pixel center relative to `vertices` in the order they were given.
+///
+/// Either winding order is accepted; degenerate triangles cover nothing.
+pub(crate) fn rasterize_triangle(
+    vertices: [(f32, f32); 3],
+    bounds: PixelRect,
+    mut visit: impl FnMut(u32, u32, [f32; 3]),
+) {
+    let Some(setup) = TriangleSetup::new(vertices, bounds) else {
+        return;
+    };
+    let [edge_bc, edge_ca, edge_ab] = setup.edges;
+    let [mut row_bc, mut row_ca, mut row_ab] = setup.origin;
+    let inverse_area = 1.0 / setup.area as f32;
+
+    for y in setup.start_y..=setup.end_y {
+        let (mut w_a, mut w_b, mut w_c) = (row_bc, row_ca, row_ab);
+
+        for x in setup.start_x..=setup.end_x {
+            if edge_bc.covers(w_a) && edge_ca.covers(w_b) && edge_ab.covers(w_c) {
+                let weight_a = w_a as f32 * inverse_area;
+                let weight_b = w_b as f32 * inverse_area;
+                let weight_c = w_c as f32 * inverse_area;
+                let weights = if setup.swapped {
+                    [weight_a, weight_c, weight_b]
+                } else {
+                    [weight_a, weight_b, weight_c]
+                };
+                visit(x as u32, y as u32, weights);
+            }
+
+            w_a += edge_bc.step_x;
+            w_b += edge_ca.step_x;
+            w_c += edge_ab.step_x;
+        }
+
+        row_bc += edge_bc.step_y;
+        row_ca += edge_ca.step_y;
+        row_ab += edge_ab.step_y;
+    }
+}
+
+/// Calls `visit(y, x0, x1)` with the covered pixels `[x0, x1)` of each row,
+/// for draws that need no per-pixel weights. Coverage is exactly that of
+/// `rasterize_triangle`; the span ends are solved from the edge functions
+/// instead of testing every pixel.
+pub(crate) fn rasterize_triangle_spans(
+    vertices: [(f32, f32); 3],
+    bounds: PixelRect,
+    mut visit: impl FnMut(u32, u32, u32),
+) {
+    let Some(setup) = TriangleSetup::new(vertices, bounds) else {
+        return;
+    };
+    let mut row = setup.origin;
+    let last = setup.end_x - setup.start_x;
+
+    for y in setup.start_y..=setup.end_y {
+        // Pixel k of the row is covered when row[i] + k * step_x >= bias for
+        // every edge, with bias 0 on top-left edges and 1 elsewhere
+        let (mut first, mut end) = (0, last);
+        for (edge, &value) in setup.edges.iter().zip(&row) {
+            let slack = value - i64::from(!edge.top_left);
+            match edge.step_x.cmp(&0) {
+                Ordering::Greater => first = first.max(-slack.div_euclid(edge.step_x)),
+                Ordering::Less => end = end.min(slack.div_euclid(-edge.step_x)),
+                Ordering::Equal if slack < 0 => end = -1,
+                Ordering::Equal => {}
+            }
+        }
+        if first <= end {
+            let x0 = (setup.start_x + first) as u32;
+            let x1 = (setup.start_x + end + 1) as u32;
+            visit(y as u32, x0, x1);
+        }
+
+        for (value, edge) in row.iter_mut().zip(&setup.edges) {
+            *value += edge.step_y;
+        }
+    }
+}
+
+/// A projected triangle corner: canvas position, window-space depth and the
+/// reciprocal of its clip-space `w`, needed for perspective-correct weights.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ScreenVertex {
+    pub x: f32,
+    pub y: f32,
+    pub depth: f32,
+    pub inverse_w: f32,
+}
+
+/// Like `rasterize_triangle`, but also interpolates depth (linearly in screen
+/// space, where `z / w` is affine) and derives perspective-correct weights
+/// from each corner's `1 / w`.
+pub(crate) fn rasterize_screen_triangle(
+    vertices: [ScreenVertex; 3],
+    bounds: PixelRect,
+    mut visit: impl FnMut(u32, u32, f32, Weights),
+) {
+    let positions = vertices.map(|vertex| (vertex.x, vertex.y));
+    let inverse_w = vertices.map(|vertex| vertex.inverse_w);
+
+    rasterize_triangle(positions, bounds, |x, y, screen| {
+        let depth = screen[0] * vertices[0].depth
+            + screen[1] * vertices[1].depth
+            + screen[2] * vertices[2].depth;
+        visit(x, y, depth, Weights::new(screen, inverse_w));
+    });
+}
+
+/// Per-pixel change of the screen-space barycentric weights along x and y.
+pub(crate) fn screen_weight_gradients(vertices: &[ScreenVertex; 3]) -> ([f32; 3], [f32; 3]) {
+    let [a, b, c] = vertices;
+    let area = (b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y);
+    if area == 0.0 {
+        return ([0.0; 3], [0.0; 3]);
+    }
+
+    let inverse_area = 1.0 / area;
+    (
+        [
+            (b.y - c.y) * inverse_area,
+            (c.y - a.y) * inverse_area,
+            (a.y - b.y) * inverse_area,
+        ],
+        [
+            (c.x - b.x) * inverse_area,
+            (a.x - c.x) * inverse_area,
+            (b.x - a.x) * inverse_area,
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: PixelRect = PixelRect {
+        x0: 0,
+        y0: 0,
+        x1: 16,
+        y1: 16,
+    };
+
+    /// Pixels covered by `vertices`, row by row.
+    fn coverage(vertices: [(f32, f32); 3]) -> Vec<(u32, u32)> {
+        let mut pixels = Vec::new();
+        rasterize_triangle(vertices, BOUNDS, |x, y, _| pixels.push((x, y)));
+        pixels
+    }
+
+    fn span_coverage(vertices: [(f32, f32); 3]) -> Vec<(u32, u32)> {
+        let mut pixels = Vec::new();
+        rasterize_triangle_spans(vertices, BOUNDS, |y, x0, x1| {
+            pixels.extend((x0..x1).map(|x| (x, y)));
+        });
+        pixels
+    }
+
+    #[test]
+    fn centers_on_top_and_left_edges_are_covered() {
+        // Every edge of the square between pixel centers (1, 1) and (5, 5)
+        // runs through a row or column of centers
+        let square = [(1.5, 1.5), (5.5, 1.5), (5.5, 5.5), (1.5, 5.5)];
+        let mut pixels = coverage([square[0], square[1], square[2]]);
+        pixels.extend(coverage([square[0], square[2], square[3]]));
+        pixels.sort_unstable();
+
+        // Left column and top row included, right column and bottom row not
+        let expected: Vec<_> = (1..5).flat_map(|x| (2..6).map(move |y| (x, y))).collect();
+        assert_eq!(pixels, expected);
+    }
+
+    #[test]
+    fn triangles_sharing_edges_cover_every_pixel_once() {
+        // A fan around a pixel center, with edges through many centers
+        let center = (7.5, 6.5);
+        let rim = [
+            (0.0, 0.0),
+            (7.5, 0.0),
+            (16.0, 0.0),
+            (16.0, 6.5),
+            (16.0, 16.0),
+            (3.5, 16.0),
+            (0.0, 16.0),
+            (0.0, 2.5),
+        ];
+        let mut counts = [[0; 16]; 16];
+        for (index, &a) in rim.iter().enumerate() {
+            let b = rim[(index + 1) % rim.len()];
+            // Alternate winding orders, which must not change coverage
+            let triangle = if index % 2 == 0 {
+                [center, a, b]
+            } else {
+                [b, a, center]
+            };
+            for (x, y) in coverage(triangle) {
+                counts[y as usize][x as usize] += 1;
+            }
+        }
+        assert!(counts.iter().flatten().all(|&count| count == 1));
+    }
+
+    #[test]
+    fn spans_match_per_pixel_coverage() {
+        let triangles = [
+            [(1.5, 1.5), (5.5, 1.5), (5.5, 5.5)],
+            [(0.3, 14.2), (9.7, 0.1), (15.9, 11.4)],
+            [(7.5, 6.5), (3.5, 16.0), (0.0, 16.0)],
+            [(-4.0, -3.0), (30.0, 2.0), (2.0, 40.0)],
+            [(2.0, 2.0), (12.0, 2.5), (2.0, 2.0001)],
+        ];
+        for triangle in triangles {
+            assert_eq!(span_coverage(triangle), coverage(triangle), "{triangle:?}");
+        }
+    }
+}
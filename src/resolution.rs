@@ -0,0 +1,87 @@
+use std::time::{Duration, Instant};
+
+/// How often the controller averages the recorded frames and adjusts the scale.
+const EVALUATION_PERIOD: Duration = Duration::from_millis(500);
+
+/// Granularity of the resolution scale, so small timing jitter never
+/// reallocates the canvas.
+pub const SCALE_STEP: f32 = 0.05;
+
+/// The scale is raised only when frames take less than this fraction of the
+/// target. Raising by one step costs roughly `(1 + step / scale)^2` more time,
+/// so the gap between this and 1.0 is the hysteresis band that keeps the
+/// controller from oscillating.
+const RAISE_THRESHOLD: f32 = 0.75;
+
+/// Picks the fraction of the window resolution the canvas renders at so that
+/// frames hold a target render time.
+///
+/// The scale drops as soon as the average frame time exceeds the target,
+/// jumping straight to the predicted fit, and recovers one step at a time once
+/// there is comfortable headroom.
+pub struct ResolutionController {
+    target_frame_time: Duration,
+    min_scale: f32,
+    max_scale: f32,
+    scale: f32,
+    total: Duration,
+    samples: u32,
+    period_start: Instant,
+}
+
+impl ResolutionController {
+    /// Starts at `max_scale`. Scales are clamped to `(0, 1]`.
+    pub fn new(target_frame_time: Duration, min_scale: f32, max_scale: f32) -> Self {
+        let max_scale = max_scale.clamp(SCALE_STEP, 1.0);
+        Self {
+            target_frame_time,
+            min_scale: min_scale.clamp(SCALE_STEP, max_scale),
+            max_scale,
+            scale: max_scale,
+            total: Duration::ZERO,
+            samples: 0,
+            period_start: Instant::now(),
+        }
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn target_frame_time(&self) -> Duration {
+        self.target_frame_time
+    }
+
+    /// Records how long a frame took to render. Returns the new scale when
+    /// it changes.
+    pub fn record_frame(&mut self, frame_time: Duration) -> Option<f32> {
+        self.total += frame_time;
+        self.samples += 1;
+        if self.period_start.elapsed() < EVALUATION_PERIOD {
+            return None;
+        }
+
+        let average = self.total.as_secs_f32() / self.samples as f32;
+        self.total = Duration::ZERO;
+        self.samples = 0;
+        self.period_start = Instant::now();
+
+        let target = self.target_frame_time.as_secs_f32();
+        let scale = if average > target {
+            // Frame time is roughly proportional to the pixel count, i.e. scale²
+            let fit = self.scale * (target / average).sqrt();
+            let fit = (fit / SCALE_STEP).floor() * SCALE_STEP;
+            fit.min(self.scale - SCALE_STEP).max(self.min_scale)
+        } else if average < target * RAISE_THRESHOLD {
+            (self.scale + SCALE_STEP).min(self.max_scale)
+        } else {
+            self.scale
+        };
+
+        if (scale - self.scale).abs() < SCALE_STEP / 2.0 {
+            return None;
+        }
+        self.scale = scale;
+        Some(scale)
+    }
+}
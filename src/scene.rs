@@ -0,0 +1,137 @@
+use anyhow::Result;
+use std::path::Path;
+use std::time::Duration;
+
+use salmon_rs::camera::{Camera, FlyController, OrbitController};
+use salmon_rs::input::Input;
+use salmon_rs::mat4::Mat4;
+use salmon_rs::mesh::Mesh;
+use salmon_rs::shader::{Fragment, Shader, VertexInput, VertexOutput};
+use salmon_rs::software_canvas::{CullMode, SoftwareCanvas};
+use salmon_rs::vec3::Vec3;
+use salmon_rs::vec4::Vec4;
+
+/// Which controller drives the camera.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CameraMode {
+    #[default]
+    Orbit,
+    Fly,
+}
+
+impl CameraMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "orbit" => Some(Self::Orbit),
+            "fly" => Some(Self::Fly),
+            _ => None,
+        }
+    }
+}
+
+/// A mesh viewed through an interactive camera.
+pub struct Scene {
+    mesh: Mesh,
+    camera: Camera,
+    mode: CameraMode,
+    orbit: OrbitController,
+    fly: FlyController,
+}
+
+impl Scene {
+    /// Loads an OBJ mesh and frames the camera on it.
+    pub fn load(path: &Path, mode: CameraMode) -> Result<Self> {
+        let mesh = Mesh::load_obj(path)?;
+        let (min, max) = mesh.bounds().unwrap_or((Vec3::ZERO, Vec3::ZERO));
+        let center = (min + max) * 0.5;
+        let radius = ((max - min).length() * 0.5).max(1e-3);
+
+        let mut camera = Camera::looking_at(center + Vec3::new(0.0, 0.5, 2.5) * radius, center);
+        camera.near = radius * 0.01;
+        camera.far = radius * 100.0;
+
+        Ok(Self {
+            mesh,
+            camera,
+            mode,
+            orbit: OrbitController::new(&camera, center),
+            fly: FlyController {
+                speed: radius,
+                ..FlyController::default()
+            },
+        })
+    }
+
+    pub fn mode(&self) -> CameraMode {
+        self.mode
+    }
+
+    /// Switches between the orbit and fly controllers, keeping the view.
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            CameraMode::Orbit => CameraMode::Fly,
+            CameraMode::Fly => {
+                // Orbit around the point the fly camera was looking at
+                let target = self.camera.position + self.camera.forward() * self.orbit.distance;
+                self.orbit = OrbitController {
+                    target,
+                    distance: self.orbit.distance,
+                    yaw: self.camera.yaw,
+                    pitch: self.camera.pitch,
+                    ..self.orbit
+                };
+                CameraMode::Orbit
+            }
+        };
+    }
+
+    /// Moves the camera from this frame's input.
+    pub fn update(&mut self, input: &Input, frame_delta: Duration) {
+        match self.mode {
+            CameraMode::Orbit => self.orbit.update(input, frame_delta, &mut self.camera),
+            CameraMode::Fly => self.fly.update(input, frame_delta, &mut self.camera),
+        }
+    }
+
+    pub fn draw(&self, canvas: &mut SoftwareCanvas) {
+        let aspect = canvas.width() as f32 / canvas.height() as f32;
+        let shader = LambertShader {
+            view_projection: self.camera.view_projection(aspect),
+            light_direction: Vec3::new(0.4, 0.8, 0.6).normalize(),
+            color: Vec4::new(0.9, 0.55, 0.45, 1.0),
+        };
+
+        canvas.clear(Vec4::new(0.1, 0.1, 0.12, 1.0));
+        canvas.set_cull_mode(CullMode::Back);
+        canvas.set_pick_object(1);
+        canvas.draw_mesh(&self.mesh, &shader);
+    }
+}
+
+/// Diffuse lighting from a single directional light, with a little ambient.
+struct LambertShader {
+    view_projection: Mat4,
+    light_direction: Vec3,
+    color: Vec4,
+}
+
+impl Shader for LambertShader {
+    type Varyings = Vec3;
+
+    fn vertex(&self, input: &VertexInput) -> VertexOutput<Vec3> {
+        VertexOutput {
+            position: self.view_projection * Vec4::from_vec3(input.position, 1.0),
+            varyings: input.normal,
+        }
+    }
+
+    fn fragment(&self, fragment: &Fragment<Vec3>) -> Option<Vec4> {
+        let diffuse = fragment
+            .varyings
+            .normalize()
+            .dot(self.light_direction)
+            .max(0.0);
+        let intensity = 0.15 + 0.85 * diffuse;
+        Some(Vec4::from_vec3(self.color.xyz() * intensity, self.color.a))
+    }
+}
@@ -0,0 +1,199 @@
+//! Programmable stages of the mesh pipeline driven by `SoftwareCanvas::draw_mesh`.
+//!
+//! A `Shader` turns each mesh corner into a clip-space position plus a set of
+//! varyings, which the rasterizer interpolates across the triangle and hands
+//! to the fragment stage for every covered pixel.
+
+use crate::vec2::Vec2;
+use crate::vec3::Vec3;
+use crate::vec4::Vec4;
+use std::ops::Sub;
+
+/// Attributes of one triangle corner, gathered from the mesh.
+#[derive(Debug, Clone, Copy)]
+pub struct VertexInput {
+    /// Object-space position
+    pub position: Vec3,
+    /// Texture coordinate, zero if the mesh has none
+    pub tex_coord: Vec2,
+    /// Vertex normal, or the face normal if the mesh has none
+    pub normal: Vec3,
+    /// Unit-length geometric normal of the triangle (counter-clockwise front face)
+    pub face_normal: Vec3,
+    /// Index of the triangle in `Mesh::triangles`
+    pub triangle: usize,
+    /// Which corner of the triangle this is, 0 to 2
+    pub corner: usize,
+}
+
+/// What the vertex stage produces for one corner.
+#[derive(Debug, Clone, Copy)]
+pub struct VertexOutput<V> {
+    /// Clip-space position, divided by `w` after clipping
+    pub position: Vec4,
+    pub varyings: V,
+}
+
+/// A covered pixel handed to the fragment stage.
+#[derive(Debug, Clone, Copy)]
+pub struct Fragment<'a, V> {
+    pub x: u32,
+    pub y: u32,
+    /// Window-space depth in `[0, 1]`
+    pub depth: f32,
+    /// Varyings interpolated to the pixel center
+    pub varyings: V,
+    /// Barycentric weights the varyings were interpolated with
+    pub weights: Weights,
+    pub(crate) interpolator: Interpolator<'a, V>,
+}
+
+/// Per-triangle data needed to interpolate varyings anywhere on screen.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Interpolator<'a, V> {
+    pub corners: [&'a V; 3],
+    pub inverse_w: [f32; 3],
+    /// Change of the screen-space weights per pixel step in x and y
+    pub screen_dx: [f32; 3],
+    pub screen_dy: [f32; 3],
+}
+
+impl<V: Varyings> Fragment<'_, V> {
+    /// Varyings interpolated at an offset, in pixels, from this pixel center.
+    pub fn varyings_at(&self, dx: f32, dy: f32) -> V {
+        let interpolator = &self.interpolator;
+        let screen = std::array::from_fn(|i| {
+            self.weights.screen[i] + dx * interpolator.screen_dx[i] + dy * interpolator.screen_dy[i]
+        });
+        V::interpolate(
+            interpolator.corners,
+            &Weights::new(screen, interpolator.inverse_w),
+        )
+    }
+
+    /// Screen-space derivative along x of a value computed from the varyings,
+    /// like GLSL's `dFdx`. Used for texture level-of-detail selection.
+    pub fn ddx<T: Sub<Output = T>>(&self, select: impl Fn(&V) -> T) -> T {
+        select(&self.varyings_at(1.0, 0.0)) - select(&self.varyings)
+    }
+
+    /// Screen-space derivative along y, like GLSL's `dFdy`.
+    pub fn ddy<T: Sub<Output = T>>(&self, select: impl Fn(&V) -> T) -> T {
+        select(&self.varyings_at(0.0, 1.0)) - select(&self.varyings)
+    }
+}
+
+/// Barycentric weights of a pixel center relative to the triangle corners.
+/// Both sets sum to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights {
+    /// Perspective-correct weights, accounting for each corner's clip-space `w`
+    pub perspective: [f32; 3],
+    /// Affine weights in screen space, ignoring depth
+    pub screen: [f32; 3],
+}
+
+impl Weights {
+    /// Derives perspective-correct weights from screen-space weights and the
+    /// reciprocal clip-space `w` of each corner.
+    pub fn new(screen: [f32; 3], inverse_w: [f32; 3]) -> Self {
+        let scaled = [
+            screen[0] * inverse_w[0],
+            screen[1] * inverse_w[1],
+            screen[2] * inverse_w[2],
+        ];
+        let total = scaled[0] + scaled[1] + scaled[2];
+        let perspective = if total != 0.0 {
+            scaled.map(|weight| weight / total)
+        } else {
+            screen
+        };
+        Self {
+            perspective,
+            screen,
+        }
+    }
+
+    /// Weights for a triangle without perspective, where both sets agree.
+    pub fn affine(weights: [f32; 3]) -> Self {
+        Self {
+            perspective: weights,
+            screen: weights,
+        }
+    }
+}
+
+/// Values the rasterizer can interpolate across a triangle. Implementations
+/// interpolate perspective-correctly unless wrapped in `NoPerspective`.
+pub trait Varyings: Copy + Send + Sync {
+    /// Blends three per-vertex values for the pixel described by `weights`.
+    fn interpolate(values: [&Self; 3], weights: &Weights) -> Self;
+}
+
+impl Varyings for () {
+    fn interpolate(_values: [&Self; 3], _weights: &Weights) -> Self {}
+}
+
+/// Interpolates the wrapped varying linearly in screen space, like GLSL's
+/// `noperspective` qualifier. Useful for screen-space effects such as
+/// wireframe edge distances.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct NoPerspective<T>(pub T);
+
+impl<T: Varyings> Varyings for NoPerspective<T> {
+    fn interpolate(values: [&Self; 3], weights: &Weights) -> Self {
+        let screen = Weights::affine(weights.screen);
+        Self(T::interpolate(values.map(|value| &value.0), &screen))
+    }
+}
+
+macro_rules! impl_varyings_for_linear {
+    ($($ty:ty),*) => {
+        $(
+            impl Varyings for $ty {
+                fn interpolate(values: [&Self; 3], weights: &Weights) -> Self {
+                    let [w0, w1, w2] = weights.perspective;
+                    *values[0] * w0 + *values[1] * w1 + *values[2] * w2
+                }
+            }
+        )*
+    };
+}
+
+impl_varyings_for_linear!(f32, Vec2, Vec3, Vec4);
+
+impl<T: Varyings, const N: usize> Varyings for [T; N] {
+    fn interpolate(values: [&Self; 3], weights: &Weights) -> Self {
+        std::array::from_fn(|i| {
+            T::interpolate([&values[0][i], &values[1][i], &values[2][i]], weights)
+        })
+    }
+}
+
+macro_rules! impl_varyings_for_tuple {
+    ($($name:ident : $index:tt),*) => {
+        impl<$($name: Varyings),*> Varyings for ($($name,)*) {
+            fn interpolate(values: [&Self; 3], weights: &Weights) -> Self {
+                ($($name::interpolate([&values[0].$index, &values[1].$index, &values[2].$index], weights),)*)
+            }
+        }
+    };
+}
+
+impl_varyings_for_tuple!(A: 0);
+impl_varyings_for_tuple!(A: 0, B: 1);
+impl_varyings_for_tuple!(A: 0, B: 1, C: 2);
+impl_varyings_for_tuple!(A: 0, B: 1, C: 2, D: 3);
+
+/// Vertex and fragment programs for `SoftwareCanvas::draw_mesh`. Fragments
+/// may be shaded on several threads at once, hence the `Sync` bound.
+pub trait Shader: Sync {
+    type Varyings: Varyings;
+
+    /// Transforms one triangle corner into clip space.
+    fn vertex(&self, input: &VertexInput) -> VertexOutput<Self::Varyings>;
+
+    /// Shades a covered pixel, or returns `None` to discard it. Discarded
+    /// fragments write neither color nor depth.
+    fn fragment(&self, fragment: &Fragment<Self::Varyings>) -> Option<Vec4>;
+}
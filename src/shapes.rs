@@ -0,0 +1,432 @@
+//! Scanline rasterization of 2D shapes: midpoint ellipses, rounded
+//! rectangles and polygons, reported as horizontal pixel spans.
+
+use crate::software_canvas::FillRule;
+use crate::vec2::Vec2;
+
+/// Largest radius the shapes accept; larger ones are clamped, which keeps the
+/// exact integer width computations within `u128`.
+const MAX_RADIUS: u32 = i32::MAX as u32;
+
+/// Half-width of the row `dy` rows from the center of an ellipse with radii
+/// `rx` and `ry` centered on a pixel, for `dy <= ry`.
+///
+/// Each row is as wide as the two decisions of the midpoint ellipse algorithm
+/// allow: along the flat top the midpoint `(x, dy - 1/2)` lies inside the
+/// ellipse, along the steep sides `(x - 1/2, dy)` does. Evaluating them per
+/// row instead of walking the whole curve gives the midpoint algorithm's
+/// circles exactly, and costs nothing for rows off the canvas.
+fn ellipse_half_width(rx: u32, ry: u32, dy: u32) -> u32 {
+    if dy == 0 || rx == 0 {
+        return rx;
+    }
+    let (a2, b2) = ((rx as u128).pow(2), (ry as u128).pow(2));
+    let dy = dy as u128;
+
+    // Largest x with 4 b² x² < a² (4 b² - (2 dy - 1)²)
+    let numerator = a2 * (4 * b2 - (2 * dy - 1).pow(2));
+    let flat = (numerator.div_ceil(4 * b2) - 1).isqrt();
+    // Largest x with (2x - 1)² <= 4 a² (b² - dy²) / b²
+    let steep = (4 * a2 * (b2 - dy * dy) / b2).isqrt().div_ceil(2);
+    flat.max(steep) as u32
+}
+
+/// A shape that covers one contiguous run of pixels in each of its rows, such
+/// as an ellipse or a rounded rectangle. Runs are computed per row, so only
+/// the rows on the canvas cost anything however large the shape is.
+pub(crate) enum RowRuns {
+    Ellipse {
+        center: (i64, i64),
+        rx: u32,
+        ry: u32,
+    },
+    RoundedRect {
+        x: i64,
+        y: i64,
+        width: u32,
+        height: u32,
+        radius: u32,
+    },
+}
+
+impl RowRuns {
+    /// Ellipse centered on pixel `center` whose extremes lie `rx` and `ry`
+    /// pixels from it.
+    pub(crate) fn ellipse(center: (i32, i32), rx: u32, ry: u32) -> Self {
+        Self::Ellipse {
+            center: (center.0 as i64, center.1 as i64),
+            rx: rx.min(MAX_RADIUS),
+            ry: ry.min(MAX_RADIUS),
+        }
+    }
+
+    /// `width x height` rectangle with bottom-left pixel `(x, y)` whose
+    /// corners are quarter circles of `radius` pixels, shrunk to fit.
+    pub(crate) fn rounded_rect(x: i32, y: i32, width: u32, height: u32, radius: u32) -> Self {
+        let radius = radius
+            .min(width.saturating_sub(1) / 2)
+            .min(height.saturating_sub(1) / 2);
+        Self::RoundedRect {
+            x: x as i64,
+            y: y as i64,
+            width,
+            height,
+            radius,
+        }
+    }
+
+    /// Bottom and top row of the shape, or `None` if it has no pixels.
+    fn rows(&self) -> Option<(i64, i64)> {
+        match *self {
+            Self::Ellipse { center, ry, .. } => Some((center.1 - ry as i64, center.1 + ry as i64)),
+            Self::RoundedRect {
+                y, width, height, ..
+            } => (width > 0 && height > 0).then(|| (y, y + height as i64 - 1)),
+        }
+    }
+
+    /// Inclusive `(left, right)` pixel range of row `y`, or `None` if the
+    /// shape doesn't reach the row.
+    fn run(&self, y: i64) -> Option<(i64, i64)> {
+        let (bottom, top) = self.rows()?;
+        if y < bottom || y > top {
+            return None;
+        }
+
+        match *self {
+            Self::Ellipse { center, rx, ry } => {
+                let dy = (y - center.1).unsigned_abs() as u32;
+                let width = ellipse_half_width(rx, ry, dy) as i64;
+                Some((center.0 - width, center.0 + width))
+            }
+            Self::RoundedRect {
+                x, width, radius, ..
+            } => {
+                // Rows within `radius` of the top or bottom edge are inset by the corner arc
+                let edge_distance = (y - bottom).min(top - y) as u32;
+                let inset = match radius.checked_sub(edge_distance) {
+                    Some(arc_row) if arc_row > 0 => {
+                        (radius - ellipse_half_width(radius, radius, arc_row)) as i64
+                    }
+                    _ => 0,
+                };
+                Some((x + inset, x + width as i64 - 1 - inset))
+            }
+        }
+    }
+
+    /// The shape's rows that lie on a canvas `canvas_height` rows high.
+    fn visible_rows(&self, canvas_height: u32) -> impl Iterator<Item = i64> {
+        let (bottom, top) = self.rows().unwrap_or((0, -1));
+        bottom.max(0)..=top.min(canvas_height as i64 - 1)
+    }
+
+    /// Calls `visit(y, left, right)` with the inclusive run of every row on a
+    /// canvas `canvas_height` rows high. Runs may extend past its sides.
+    pub(crate) fn fill(&self, canvas_height: u32, mut visit: impl FnMut(i64, i64, i64)) {
+        for y in self.visible_rows(canvas_height) {
+            if let Some((left, right)) = self.run(y) {
+                visit(y, left, right);
+            }
+        }
+    }
+
+    /// Like `fill`, for the shape's one-pixel boundary: the pixels not covered
+    /// by both neighbouring rows. Every pixel is visited once.
+    pub(crate) fn outline(&self, canvas_height: u32, mut visit: impl FnMut(i64, i64, i64)) {
+        for y in self.visible_rows(canvas_height) {
+            let Some((left, right)) = self.run(y) else {
+                continue;
+            };
+            let (Some(below), Some(above)) = (self.run(y - 1), self.run(y + 1)) else {
+                // The top and bottom rows are all boundary
+                visit(y, left, right);
+                continue;
+            };
+
+            let inner_left = below.0.max(above.0);
+            let inner_right = below.1.min(above.1);
+            let left_end = (inner_left - 1).max(left);
+            let right_start = (inner_right + 1).min(right);
+            if left_end + 1 >= right_start {
+                visit(y, left, right);
+            } else {
+                visit(y, left, left_end);
+                visit(y, right_start, right);
+            }
+        }
+    }
+}
+
+/// Non-horizontal polygon edge, oriented bottom to top.
+struct Edge {
+    bottom: Vec2,
+    top: Vec2,
+    /// +1 if the contour runs upwards along the edge, -1 if downwards
+    winding: i32,
+}
+
+impl Edge {
+    fn x_at(&self, y: f32) -> f32 {
+        let t = (y - self.bottom.y) / (self.top.y - self.bottom.y);
+        self.bottom.x + (self.top.x - self.bottom.x) * t
+    }
+}
+
+/// Calls `visit(y, x0, x1)` with the pixels of every row of `canvas_size`
+/// that the closed `contours` cover under `rule`; `x1` is exclusive.
+///
+/// Coordinates are continuous with pixel centers at `x + 0.5, y + 0.5`, and a
+/// pixel is covered when its center is, counting centers on a left or bottom
+/// edge as inside, so polygons sharing an edge neither overlap nor leave gaps.
+pub(crate) fn polygon_spans<C: AsRef<[Vec2]>>(
+    contours: &[C],
+    rule: FillRule,
+    canvas_size: (u32, u32),
+    mut visit: impl FnMut(u32, u32, u32),
+) {
+    let (width, height) = canvas_size;
+    let mut edges: Vec<Edge> = Vec::new();
+    for contour in contours {
+        let points = contour.as_ref();
+        for (index, &start) in points.iter().enumerate() {
+            let end = points[(index + 1) % points.len()];
+            if start.y == end.y || !(start.is_finite() && end.is_finite()) {
+                continue;
+            }
+            edges.push(if start.y < end.y {
+                Edge {
+                    bottom: start,
+                    top: end,
+                    winding: 1,
+                }
+            } else {
+                Edge {
+                    bottom: end,
+                    top: start,
+                    winding: -1,
+                }
+            });
+        }
+    }
+    if edges.is_empty() || width == 0 || height == 0 {
+        return;
+    }
+    edges.sort_by(|a, b| a.bottom.y.total_cmp(&b.bottom.y));
+
+    // First row whose center lies on or above the lowest edge
+    let first_row = (edges[0].bottom.y - 0.5).ceil().clamp(0.0, height as f32) as u32;
+    let mut next_edge = 0;
+    let mut active: Vec<&Edge> = Vec::new();
+    let mut crossings: Vec<(f32, i32)> = Vec::new();
+
+    for y in first_row..height {
+        let center = y as f32 + 0.5;
+
+        // Edges span their rows half-open, bottom included and top excluded
+        while let Some(edge) = edges.get(next_edge)
+            && edge.bottom.y <= center
+        {
+            active.push(edge);
+            next_edge += 1;
+        }
+        active.retain(|edge| edge.top.y > center);
+        if active.is_empty() {
+            if next_edge == edges.len() {
+                break;
+            }
+            continue;
+        }
+
+        crossings.clear();
+        crossings.extend(active.iter().map(|edge| (edge.x_at(center), edge.winding)));
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut winding = 0;
+        let mut span_start = 0.0;
+        for &(x, edge_winding) in &crossings {
+            let was_inside = rule.is_inside(winding);
+            winding += edge_winding;
+            match (was_inside, rule.is_inside(winding)) {
+                (false, true) => span_start = x,
+                (true, false) => {
+                    // Columns whose centers lie in [span_start, x)
+                    let x0 = (span_start - 0.5).ceil().clamp(0.0, width as f32) as u32;
+                    let x1 = (x - 0.5).ceil().clamp(0.0, width as f32) as u32;
+                    if x0 < x1 {
+                        visit(y, x0, x1);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn filled(shape: &RowRuns, canvas_height: u32) -> HashSet<(i64, i64)> {
+        let mut pixels = HashSet::new();
+        shape.fill(canvas_height, |y, left, right| {
+            pixels.extend((left..=right).map(|x| (x, y)));
+        });
+        pixels
+    }
+
+    /// Pixels of `pixels` with a 4-connected neighbour outside it.
+    fn boundary(pixels: &HashSet<(i64, i64)>) -> HashSet<(i64, i64)> {
+        pixels
+            .iter()
+            .copied()
+            .filter(|&(x, y)| {
+                [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+                    .iter()
+                    .any(|neighbour| !pixels.contains(neighbour))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ellipse_runs_are_symmetric_and_cover_its_area() {
+        let center = (200, 200);
+        for (rx, ry) in [
+            (0, 0),
+            (1, 1),
+            (5, 5),
+            (12, 4),
+            (3, 17),
+            (40, 25),
+            (100, 100),
+        ] {
+            let ellipse = RowRuns::ellipse(center, rx, ry);
+            let mut rows = 0;
+            let mut area = 0;
+            ellipse.fill(400, |y, left, right| {
+                rows += 1;
+                area += right - left + 1;
+                assert_eq!(left + right, 2 * center.0 as i64, "{rx}x{ry} row {y}");
+                let mirrored = 2 * center.1 as i64 - y;
+                assert_eq!(
+                    ellipse.run(mirrored),
+                    Some((left, right)),
+                    "{rx}x{ry} row {y}"
+                );
+            });
+
+            assert_eq!(rows, 2 * ry as i64 + 1);
+            assert_eq!(
+                ellipse.run(center.1 as i64),
+                Some((200 - rx as i64, 200 + rx as i64))
+            );
+            // Pixel centers within the ellipse grown by half a pixel, give or
+            // take the pixels its boundary passes through
+            let expected = std::f64::consts::PI * (rx as f64 + 0.5) * (ry as f64 + 0.5);
+            assert!(
+                (area as f64 - expected).abs() <= (rx + ry + 1) as f64,
+                "{rx}x{ry}: {area} pixels, expected about {expected:.0}"
+            );
+        }
+    }
+
+    #[test]
+    fn outline_is_the_boundary_of_the_fill() {
+        let shapes = [
+            RowRuns::ellipse((30, 30), 20, 11),
+            RowRuns::ellipse((30, 30), 3, 25),
+            RowRuns::rounded_rect(4, 6, 40, 30, 9),
+            RowRuns::rounded_rect(4, 6, 7, 2, 5),
+        ];
+        for shape in shapes {
+            let mut outline = Vec::new();
+            shape.outline(100, |y, left, right| {
+                outline.extend((left..=right).map(|x| (x, y)));
+            });
+            let unique: HashSet<_> = outline.iter().copied().collect();
+
+            assert_eq!(unique.len(), outline.len(), "pixels visited twice");
+            assert_eq!(unique, boundary(&filled(&shape, 100)));
+        }
+    }
+
+    #[test]
+    fn rounded_rect_without_radius_is_a_rectangle() {
+        let rect = filled(&RowRuns::rounded_rect(-3, 2, 10, 4, 0), 100);
+        let expected: HashSet<_> = (-3..7).flat_map(|x| (2..6).map(move |y| (x, y))).collect();
+        assert_eq!(rect, expected);
+
+        // Corners are cut, and the radius shrinks to fit
+        let rounded = RowRuns::rounded_rect(0, 0, 10, 4, 50);
+        assert_eq!(rounded.run(0), Some((1, 8)));
+        assert_eq!(rounded.run(1), Some((0, 9)));
+        assert!(filled(&RowRuns::rounded_rect(0, 0, 0, 4, 2), 100).is_empty());
+    }
+
+    #[test]
+    fn only_rows_on_the_canvas_are_visited() {
+        let mut rows = Vec::new();
+        let huge = RowRuns::ellipse((i32::MIN, 5), u32::MAX, u32::MAX);
+        huge.fill(8, |y, left, right| {
+            assert!(left < right);
+            rows.push(y);
+        });
+        assert_eq!(rows, (0..8).collect::<Vec<_>>());
+
+        let below = RowRuns::ellipse((0, -50), 10, 10);
+        below.fill(8, |_, _, _| panic!("row off the canvas"));
+    }
+
+    fn polygon_pixels(points: &[Vec2], rule: FillRule) -> HashSet<(u32, u32)> {
+        let mut pixels = HashSet::new();
+        polygon_spans(&[points], rule, (64, 64), |y, x0, x1| {
+            for x in x0..x1 {
+                assert!(pixels.insert((x, y)), "pixel {x},{y} visited twice");
+            }
+        });
+        pixels
+    }
+
+    #[test]
+    fn concave_polygon_fills_the_same_under_both_rules() {
+        // A square with a notch cut into its right side
+        let points = [
+            (8.0, 8.0),
+            (56.0, 8.0),
+            (56.0, 24.0),
+            (24.0, 24.0),
+            (24.0, 40.0),
+            (56.0, 40.0),
+            (56.0, 56.0),
+            (8.0, 56.0),
+        ]
+        .map(|(x, y)| Vec2::new(x, y));
+
+        for rule in [FillRule::NonZero, FillRule::EvenOdd] {
+            let pixels = polygon_pixels(&points, rule);
+            assert_eq!(pixels.len(), 48 * 48 - 32 * 16, "{rule:?}");
+            assert!(pixels.contains(&(8, 8)) && pixels.contains(&(55, 55)));
+            assert!(!pixels.contains(&(40, 32)) && !pixels.contains(&(56, 8)));
+        }
+    }
+
+    #[test]
+    fn self_intersecting_polygon_depends_on_the_rule() {
+        // A pentagram, whose center is wound around twice
+        let points: Vec<Vec2> = (0..5)
+            .map(|i| {
+                let angle =
+                    std::f32::consts::FRAC_PI_2 + i as f32 * 4.0 * std::f32::consts::PI / 5.0;
+                Vec2::new(32.0 + 28.0 * angle.cos(), 32.0 + 28.0 * angle.sin())
+            })
+            .collect();
+        let non_zero = polygon_pixels(&points, FillRule::NonZero);
+        let even_odd = polygon_pixels(&points, FillRule::EvenOdd);
+
+        let (center, tip) = ((32, 32), (32, 56));
+        assert!(non_zero.contains(&center) && non_zero.contains(&tip));
+        assert!(!even_odd.contains(&center) && even_odd.contains(&tip));
+        assert!(even_odd.is_subset(&non_zero));
+    }
+}
@@ -0,0 +1,153 @@
+//! Vectorized bulk pixel operations.
+//!
+//! Each operation picks an AVX2 implementation at runtime when the CPU supports
+//! it and falls back to plain loops otherwise. Both paths produce identical
+//! results; `set_enabled(false)` forces the fallback, which is how the
+//! benchmarks measure the speedup.
+//!
+//! Only operations that measurably beat the compiler's own code live here.
+//! Fills are left to `slice::fill`, which already compiles to vectorized
+//! stores: hand-written AVX2 fills, with or without streaming stores, ran at
+//! 0.87-1.14x of it for canvas clears and triangle spans.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Allows or forbids the vectorized paths for the whole process.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether a vectorized path is enabled and supported by this CPU.
+pub fn is_accelerated() -> bool {
+    has_avx2()
+}
+
+#[cfg(target_arch = "x86_64")]
+fn has_avx2() -> bool {
+    ENABLED.load(Ordering::Relaxed) && std::arch::is_x86_feature_detected!("avx2")
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn has_avx2() -> bool {
+    false
+}
+
+/// Source indices for `gather_u32`, validated once against the length of the
+/// rows they will index.
+#[derive(Debug, Clone)]
+pub struct GatherIndices {
+    indices: Vec<i32>,
+    source_len: usize,
+}
+
+impl GatherIndices {
+    /// Panics if an index is not below `source_len`.
+    pub fn new(indices: impl IntoIterator<Item = usize>, source_len: usize) -> Self {
+        assert!(source_len <= i32::MAX as usize, "gather source too long");
+        let indices = indices
+            .into_iter()
+            .map(|index| {
+                assert!(index < source_len, "gather index {index} out of range");
+                index as i32
+            })
+            .collect();
+        Self {
+            indices,
+            source_len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn source_len(&self) -> usize {
+        self.source_len
+    }
+}
+
+/// Writes `source[indices[i]]` to `target[i]` for every index.
+///
+/// Panics if `source` or `target` don't match the lengths `indices` was built for.
+pub fn gather_u32(target: &mut [u32], source: &[u32], indices: &GatherIndices) {
+    assert_eq!(source.len(), indices.source_len, "gather source length");
+    assert_eq!(target.len(), indices.len(), "gather target length");
+
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // SAFETY: AVX2 support was checked above and every index is in bounds
+        // of `source`, which has the length the indices were validated against
+        unsafe { x86::gather_u32(target, source, &indices.indices) };
+        return;
+    }
+    scalar::gather_u32(target, source, &indices.indices);
+}
+
+/// Plain-loop versions of every operation.
+pub mod scalar {
+    pub fn gather_u32(target: &mut [u32], source: &[u32], indices: &[i32]) {
+        for (pixel, &index) in target.iter_mut().zip(indices) {
+            *pixel = source[index as usize];
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    const LANES: usize = 8;
+
+    /// Caller guarantees every index is in bounds of `source`.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn gather_u32(target: &mut [u32], source: &[u32], indices: &[i32]) {
+        let base = source.as_ptr().cast::<i32>();
+        let mut targets = target.chunks_exact_mut(LANES);
+        let mut lanes = indices.chunks_exact(LANES);
+        for (chunk, index) in (&mut targets).zip(&mut lanes) {
+            // SAFETY: 8 indices are loaded unaligned, all in bounds of `source`
+            // per the caller's contract, and stored into an 8-element chunk
+            unsafe {
+                let index = _mm256_loadu_si256(index.as_ptr().cast());
+                let pixels = _mm256_i32gather_epi32::<4>(base, index);
+                _mm256_storeu_si256(chunk.as_mut_ptr().cast(), pixels);
+            }
+        }
+        for (pixel, &index) in targets.into_remainder().iter_mut().zip(lanes.remainder()) {
+            *pixel = source[index as usize];
+        }
+    }
+}
+
+#[cfg(all(test, target_arch = "x86_64"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn avx2_gather_matches_scalar() {
+        if !std::arch::is_x86_feature_detected!("avx2") {
+            eprintln!("skipping: AVX2 is not available");
+            return;
+        }
+
+        let source: Vec<u32> = (0..97).map(|i| 0x0101_0101 * i).collect();
+        // Widths around and between the 8-lane chunks, including empty rows
+        for width in (0..=41).chain([255, 1023]) {
+            let indices: Vec<i32> = (0..width)
+                .map(|x| ((x * 37 + 11) % source.len()) as i32)
+                .collect();
+            let mut expected = vec![0; width];
+            let mut actual = vec![0; width];
+            scalar::gather_u32(&mut expected, &source, &indices);
+            // SAFETY: AVX2 support was checked above and every index is in bounds
+            unsafe { x86::gather_u32(&mut actual, &source, &indices) };
+            assert_eq!(actual, expected, "width {width}");
+        }
+    }
+}
@@ -1,13 +1,137 @@
+use crate::bezier;
+use crate::blend::BlendMode;
+use crate::blit::{self, ScaleFilter, ScaleMode};
+use crate::clip::{self, FRUSTUM_PLANES};
+use crate::image_io;
+use crate::line;
+use crate::mesh::Mesh;
+use crate::pipeline::{self, RasterState, SetupTriangle};
+use crate::rasterizer::{PixelRect, ScreenVertex, rasterize_triangle, rasterize_triangle_spans};
+use crate::shader::{Shader, Varyings, VertexInput, VertexOutput};
+use crate::shapes::{self, RowRuns};
+use crate::stroke::{self, StrokeStyle};
+use crate::vec2::Vec2;
 use crate::vec4::Vec4;
+use crate::worker_pool::WorkerPool;
 use anyhow::Result;
 use std::num::NonZeroU32;
+use std::path::Path;
 use std::sync::Arc;
 use winit::window::Window;
 
+/// Comparison used by the depth test; a fragment passes when
+/// `compare(fragment_depth, stored_depth)` holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DepthFunc {
+    #[default]
+    Less,
+    LessEqual,
+    Always,
+    Never,
+}
+
+impl DepthFunc {
+    pub fn passes(self, depth: f32, stored: f32) -> bool {
+        match self {
+            Self::Less => depth < stored,
+            Self::LessEqual => depth <= stored,
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+/// Which triangle faces `draw_mesh` skips. Front faces wind counter-clockwise
+/// on screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CullMode {
+    #[default]
+    None,
+    Back,
+    Front,
+}
+
+/// How `draw_clip_space_line` rasterizes lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineMode {
+    /// Integer Bresenham, one fully covered pixel per step
+    #[default]
+    Aliased,
+    /// Xiaolin Wu lines with coverage blended as alpha
+    AntiAliased,
+}
+
+/// Which points `fill_polygon` treats as inside a self-intersecting polygon
+/// or one with several contours, from the number of times they are wound around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FillRule {
+    /// Inside when wound around an odd number of times, so overlaps alternate
+    EvenOdd,
+    /// Inside when the contours' windings don't cancel out
+    #[default]
+    NonZero,
+}
+
+impl FillRule {
+    /// Whether a point with this winding number is filled.
+    pub fn is_inside(self, winding: i32) -> bool {
+        match self {
+            Self::EvenOdd => winding % 2 != 0,
+            Self::NonZero => winding != 0,
+        }
+    }
+}
+
+/// Whether the canvas resolution is fixed or tracks the window.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CanvasSizing {
+    /// Keep the resolution given to `new` or `set_canvas_size`
+    #[default]
+    Fixed,
+    /// Resize to this fraction of the window size whenever it changes
+    FollowWindow(f32),
+}
+
+/// What drew a pixel, as recorded in the pick buffer: the object ID set with
+/// `set_pick_object` and the index of the triangle within that draw call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PickId {
+    pub object: u32,
+    pub primitive: u32,
+}
+
+/// Depth the depth buffer is reset to by `clear`, i.e. the far plane.
+pub const DEPTH_CLEAR_VALUE: f32 = 1.0;
+
+/// Largest canvas width or height the command line accepts.
+pub const MAX_CANVAS_DIMENSION: u32 = 1 << 14;
+
 pub struct SoftwareCanvas {
     canvas_size: (u32, u32),
     window_size: (u32, u32),
 
+    // Canvas-sized ARGB framebuffer that all drawing targets.
+    // Rows are stored bottom-up: index 0 is the bottom-left pixel.
+    framebuffer: Vec<u32>,
+
+    // Per-pixel depth in [0, 1] (0 = near), same layout as the framebuffer
+    depth_buffer: Vec<f32>,
+    depth_func: DepthFunc,
+    depth_write: bool,
+    cull_mode: CullMode,
+    // Extra clip-space planes applied after the view frustum
+    clip_planes: Vec<Vec4>,
+    line_mode: LineMode,
+    blend_mode: BlendMode,
+    // Threads `draw_mesh` shades with, kept alive between draws
+    workers: WorkerPool,
+    scale_mode: ScaleMode,
+    scale_filter: ScaleFilter,
+    sizing: CanvasSizing,
+    // Per-pixel IDs of what drew each pixel, same layout as the framebuffer
+    pick_buffer: Option<Vec<Option<PickId>>>,
+    pick_object: u32,
+
     // Graphics infrastructure
     surface: Option<softbuffer::Surface<Arc<Window>, Arc<Window>>>,
     context: Option<softbuffer::Context<Arc<Window>>>,
@@ -20,6 +144,22 @@ impl SoftwareCanvas {
         Self {
             canvas_size,
             window_size: (0, 0),
+            framebuffer: vec![Vec4::black().to_argb(); buffer_len(canvas_size)],
+            depth_buffer: vec![DEPTH_CLEAR_VALUE; buffer_len(canvas_size)],
+            depth_func: DepthFunc::default(),
+            depth_write: true,
+            cull_mode: CullMode::default(),
+            clip_planes: Vec::new(),
+            line_mode: LineMode::default(),
+            blend_mode: BlendMode::default(),
+            workers: WorkerPool::new(
+                std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+            ),
+            scale_mode: ScaleMode::default(),
+            scale_filter: ScaleFilter::default(),
+            sizing: CanvasSizing::default(),
+            pick_buffer: None,
+            pick_object: 0,
             surface: None,
             context: None,
             current_surface_size: None,
@@ -54,94 +194,668 @@ impl SoftwareCanvas {
             return;
         }
 
-        if let Some(surface) = &mut self.surface {
-            if let Ok(mut buffer) = surface.buffer_mut() {
-                let scale_x = self.window_size.0 as f32 / self.canvas_size.0 as f32;
-                let scale_y = self.window_size.1 as f32 / self.canvas_size.1 as f32;
-
-                // Calculate the range of surface pixels this canvas pixel covers
-                let start_x = (x as f32 * scale_x) as u32;
-                let end_x = ((x + 1) as f32 * scale_x) as u32;
-                let start_y = (y as f32 * scale_y) as u32;
-                let end_y = ((y + 1) as f32 * scale_y) as u32;
-
-                let argb_color = color.to_argb();
-
-                for surface_y in start_y..end_y.min(self.window_size.1) {
-                    for surface_x in start_x..end_x.min(self.window_size.0) {
-                        // Flip Y coordinate for surface
-                        let real_y = self.window_size.1 - 1 - surface_y;
-                        let index = (real_y * self.window_size.0 + surface_x) as usize;
-
-                        if index < buffer.len() {
-                            buffer[index] = argb_color;
-                        }
+        let index = pixel_index(self.canvas_size.0, x, y);
+        self.blend_mode.write(&mut self.framebuffer[index], color);
+    }
+
+    /// Reads back a canvas pixel, or `None` if the coordinates are out of range.
+    pub fn get_pixel(&self, x: u32, y: u32) -> Option<Vec4> {
+        if x >= self.canvas_size.0 || y >= self.canvas_size.1 {
+            return None;
+        }
+
+        let index = pixel_index(self.canvas_size.0, x, y);
+        Some(Vec4::from_argb(self.framebuffer[index]))
+    }
+
+    /// The raw ARGB framebuffer, `width * height` pixels stored bottom row first.
+    pub fn pixels(&self) -> &[u32] {
+        &self.framebuffer
+    }
+
+    /// Mutable access to the raw framebuffer, in the layout of `pixels`.
+    pub fn pixels_mut(&mut self) -> &mut [u32] {
+        &mut self.framebuffer
+    }
+
+    /// Writes the canvas to an image file; the format follows the file extension.
+    pub fn save_image(&self, path: &Path) -> Result<()> {
+        image_io::write_image(
+            path,
+            self.canvas_size.0,
+            self.canvas_size.1,
+            &self.framebuffer,
+        )
+    }
+
+    /// Replaces the canvas contents with an image file, resizing the canvas
+    /// to match it. The depth buffer is cleared.
+    pub fn load_image(&mut self, path: &Path) -> Result<()> {
+        let image = image_io::read_image(path)?;
+        self.set_canvas_size(image.width, image.height);
+        self.framebuffer = image.pixels;
+        Ok(())
+    }
+
+    /// Clears the color buffer to `color` and the depth buffer to the far plane.
+    pub fn clear(&mut self, color: Vec4) {
+        self.framebuffer.fill(color.to_argb());
+        self.depth_buffer.fill(DEPTH_CLEAR_VALUE);
+        if let Some(pick_buffer) = &mut self.pick_buffer {
+            pick_buffer.fill(None);
+        }
+    }
+
+    pub fn picking(&self) -> bool {
+        self.pick_buffer.is_some()
+    }
+
+    /// Enables or disables the pick buffer. While enabled, triangle draws
+    /// record a `PickId` for every pixel they write; `clear` resets it.
+    pub fn set_picking(&mut self, enabled: bool) {
+        if enabled != self.picking() {
+            let len = self.framebuffer.len();
+            self.pick_buffer = enabled.then(|| vec![None; len]);
+        }
+    }
+
+    pub fn pick_object(&self) -> u32 {
+        self.pick_object
+    }
+
+    /// Sets the object ID recorded by subsequent draws.
+    pub fn set_pick_object(&mut self, object: u32) {
+        self.pick_object = object;
+    }
+
+    /// What last drew canvas pixel `(x, y)`, or `None` if nothing did, the
+    /// coordinates are out of range or picking is disabled.
+    pub fn pick(&self, x: u32, y: u32) -> Option<PickId> {
+        if x >= self.canvas_size.0 || y >= self.canvas_size.1 {
+            return None;
+        }
+        self.pick_buffer.as_ref()?[pixel_index(self.canvas_size.0, x, y)]
+    }
+
+    /// Like `pick`, for a window position such as the cursor; see `window_to_canvas`.
+    pub fn pick_window(&self, x: f32, y: f32) -> Option<PickId> {
+        let position = self.window_to_canvas(x, y)?;
+        self.pick(position.x as u32, position.y as u32)
+    }
+
+    /// Reads back a depth buffer value, or `None` if the coordinates are out of range.
+    pub fn get_depth(&self, x: u32, y: u32) -> Option<f32> {
+        if x >= self.canvas_size.0 || y >= self.canvas_size.1 {
+            return None;
+        }
+
+        Some(self.depth_buffer[pixel_index(self.canvas_size.0, x, y)])
+    }
+
+    pub fn depth_func(&self) -> DepthFunc {
+        self.depth_func
+    }
+
+    pub fn set_depth_func(&mut self, depth_func: DepthFunc) {
+        self.depth_func = depth_func;
+    }
+
+    pub fn depth_write(&self) -> bool {
+        self.depth_write
+    }
+
+    /// Enables or disables depth buffer updates for fragments that pass the depth test.
+    pub fn set_depth_write(&mut self, enabled: bool) {
+        self.depth_write = enabled;
+    }
+
+    /// Draws a one-pixel line between two pixel positions with integer
+    /// Bresenham. Endpoints may lie off-canvas; the line is clipped first.
+    pub fn draw_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: Vec4) {
+        let width = self.canvas_size.0;
+        let blend_mode = self.blend_mode;
+        let framebuffer = &mut self.framebuffer;
+
+        line::bresenham((x1, y1), (x2, y2), self.canvas_size, |x, y| {
+            blend_mode.write(&mut framebuffer[pixel_index(width, x, y)], color);
+        });
+    }
+
+    /// Draws an anti-aliased line with Xiaolin Wu's algorithm. Coordinates are
+    /// continuous canvas coordinates (pixel centers at `x + 0.5, y + 0.5`) and
+    /// partially covered pixels are blended with coverage scaling alpha.
+    pub fn draw_line_aa(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, color: Vec4) {
+        let width = self.canvas_size.0;
+        let blend_mode = self.blend_mode;
+        let framebuffer = &mut self.framebuffer;
+
+        line::wu(
+            (x1 - 0.5, y1 - 0.5),
+            (x2 - 0.5, y2 - 0.5),
+            self.canvas_size,
+            |x, y, coverage| {
+                let (mode, source) = blend_mode.apply_coverage(color, coverage);
+                mode.write(&mut framebuffer[pixel_index(width, x, y)], source);
+            },
+        );
+    }
+
+    pub fn line_mode(&self) -> LineMode {
+        self.line_mode
+    }
+
+    /// Selects how `draw_clip_space_line` rasterizes.
+    pub fn set_line_mode(&mut self, line_mode: LineMode) {
+        self.line_mode = line_mode;
+    }
+
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    /// Sets how every subsequent pixel write combines with the framebuffer.
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    /// Paints the 4-connected region of pixels that share the exact color of
+    /// `(x, y)`, like a paint bucket. Out-of-range seeds are ignored.
+    pub fn flood_fill(&mut self, x: u32, y: u32, color: Vec4) {
+        let (width, height) = self.canvas_size;
+        if x >= width || y >= height {
+            return;
+        }
+
+        // Find the whole region before writing, since a blended write may
+        // leave a pixel with the color being replaced
+        let framebuffer = &self.framebuffer;
+        let target = framebuffer[pixel_index(width, x, y)];
+        let is_open =
+            |region: &[bool], index: usize| !region[index] && framebuffer[index] == target;
+        let mut region = vec![false; framebuffer.len()];
+        let mut seeds = vec![(x, y)];
+
+        while let Some((x, y)) = seeds.pop() {
+            let row = pixel_index(width, 0, y);
+            if !is_open(&region, row + x as usize) {
+                continue;
+            }
+
+            // Extend the seed to the whole horizontal run it lies in
+            let mut left = x;
+            while left > 0 && is_open(&region, row + left as usize - 1) {
+                left -= 1;
+            }
+            let mut right = x;
+            while right + 1 < width && is_open(&region, row + right as usize + 1) {
+                right += 1;
+            }
+            region[row + left as usize..=row + right as usize].fill(true);
+
+            // Seed one pixel of every open run directly above and below
+            let neighbours = [y.checked_sub(1), (y + 1 < height).then_some(y + 1)];
+            for neighbour in neighbours.into_iter().flatten() {
+                let row = pixel_index(width, 0, neighbour);
+                let mut in_run = false;
+                for x in left..=right {
+                    let open = is_open(&region, row + x as usize);
+                    if open && !in_run {
+                        seeds.push((x, neighbour));
                     }
+                    in_run = open;
                 }
             }
         }
+
+        for (pixel, _) in self
+            .framebuffer
+            .iter_mut()
+            .zip(&region)
+            .filter(|(_, inside)| **inside)
+        {
+            self.blend_mode.write(pixel, color);
+        }
     }
 
-    pub fn clear(&mut self, color: Vec4) {
-        if let Some(surface) = &mut self.surface {
-            if let Ok(mut buffer) = surface.buffer_mut() {
-                let argb_color = color.to_argb();
-                for pixel in buffer.iter_mut() {
-                    *pixel = argb_color;
-                }
+    /// Fills the triangle `a, b, c` given in canvas coordinates (pixel centers
+    /// lie at `x + 0.5, y + 0.5`). Pixels exactly on a shared edge follow the
+    /// top-left rule, so adjacent triangles neither overlap nor leave cracks.
+    pub fn fill_triangle(&mut self, a: (f32, f32), b: (f32, f32), c: (f32, f32), color: Vec4) {
+        let bounds = PixelRect::new(0, 0, self.canvas_size.0, self.canvas_size.1);
+        rasterize_triangle_spans([a, b, c], bounds, |y, x0, x1| {
+            self.fill_span(y, x0, x1, color);
+        });
+    }
+
+    /// Writes pixels `x0..x1` of row `y`, which must lie on the canvas, and
+    /// records them in the pick buffer.
+    fn fill_span(&mut self, y: u32, x0: u32, x1: u32, color: Vec4) {
+        let width = self.canvas_size.0;
+        let range = pixel_index(width, x0, y)..pixel_index(width, x1, y);
+        let span = &mut self.framebuffer[range.clone()];
+        if self.blend_mode == BlendMode::Replace {
+            span.fill(color.to_argb());
+        } else {
+            for pixel in span {
+                self.blend_mode.write(pixel, color);
             }
         }
+        let pick_id = self.current_pick_id();
+        if let Some(pick_buffer) = self.pick_buffer.as_deref_mut() {
+            pick_buffer[range].fill(pick_id);
+        }
+    }
+
+    /// Like `fill_span` for the inclusive pixel range `x0..=x1` of row `y`,
+    /// clipped to the canvas.
+    fn fill_row(&mut self, y: i64, x0: i64, x1: i64, color: Vec4) {
+        let (width, height) = (self.canvas_size.0 as i64, self.canvas_size.1 as i64);
+        if y < 0 || y >= height || x1 < 0 || x0 >= width || x0 > x1 {
+            return;
+        }
+        let x1 = (x1 + 1).min(width);
+        self.fill_span(y as u32, x0.max(0) as u32, x1 as u32, color);
+    }
+
+    /// Outlines the `width x height` rectangle whose bottom-left pixel is `(x, y)`.
+    pub fn draw_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: Vec4) {
+        self.draw_rounded_rect(x, y, width, height, 0, color);
+    }
+
+    /// Fills the `width x height` rectangle whose bottom-left pixel is `(x, y)`.
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: Vec4) {
+        self.fill_rounded_rect(x, y, width, height, 0, color);
+    }
+
+    /// Like `draw_rect` with corners rounded to quarter circles of `radius`
+    /// pixels, shrunk if the rectangle is too small for them.
+    pub fn draw_rounded_rect(
+        &mut self,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        radius: u32,
+        color: Vec4,
+    ) {
+        RowRuns::rounded_rect(x, y, width, height, radius)
+            .outline(self.canvas_size.1, |y, x0, x1| {
+                self.fill_row(y, x0, x1, color)
+            });
+    }
+
+    /// Like `fill_rect` with corners rounded to quarter circles of `radius`
+    /// pixels, shrunk if the rectangle is too small for them.
+    pub fn fill_rounded_rect(
+        &mut self,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        radius: u32,
+        color: Vec4,
+    ) {
+        RowRuns::rounded_rect(x, y, width, height, radius).fill(self.canvas_size.1, |y, x0, x1| {
+            self.fill_row(y, x0, x1, color)
+        });
+    }
+
+    /// Outlines the circle of `radius` pixels around pixel `(cx, cy)` with
+    /// the midpoint algorithm.
+    pub fn draw_circle(&mut self, cx: i32, cy: i32, radius: u32, color: Vec4) {
+        self.draw_ellipse(cx, cy, radius, radius, color);
+    }
+
+    /// Fills the circle of `radius` pixels around pixel `(cx, cy)`.
+    pub fn fill_circle(&mut self, cx: i32, cy: i32, radius: u32, color: Vec4) {
+        self.fill_ellipse(cx, cy, radius, radius, color);
+    }
+
+    /// Outlines the axis-aligned ellipse around pixel `(cx, cy)` with radii
+    /// `rx` and `ry`, using the midpoint algorithm. Every pixel is written once.
+    pub fn draw_ellipse(&mut self, cx: i32, cy: i32, rx: u32, ry: u32, color: Vec4) {
+        RowRuns::ellipse((cx, cy), rx, ry).outline(self.canvas_size.1, |y, x0, x1| {
+            self.fill_row(y, x0, x1, color)
+        });
+    }
+
+    /// Fills the axis-aligned ellipse around pixel `(cx, cy)` with radii `rx`
+    /// and `ry`, matching the pixels `draw_ellipse` outlines.
+    pub fn fill_ellipse(&mut self, cx: i32, cy: i32, rx: u32, ry: u32, color: Vec4) {
+        RowRuns::ellipse((cx, cy), rx, ry).fill(self.canvas_size.1, |y, x0, x1| {
+            self.fill_row(y, x0, x1, color)
+        });
+    }
+
+    /// Fills the closed polygon through `points`, given in canvas coordinates
+    /// (pixel centers at `x + 0.5, y + 0.5`), with scanline conversion.
+    pub fn fill_polygon(&mut self, points: &[Vec2], rule: FillRule, color: Vec4) {
+        self.fill_contours(&[points], rule, color);
+    }
+
+    /// Fills the area enclosed by several closed contours at once, e.g. a
+    /// shape with holes, deciding what is inside with `rule`. Pixels covered
+    /// by more than one contour are written once.
+    pub fn fill_contours<C: AsRef<[Vec2]>>(&mut self, contours: &[C], rule: FillRule, color: Vec4) {
+        shapes::polygon_spans(contours, rule, self.canvas_size, |y, x0, x1| {
+            self.fill_span(y, x0, x1, color);
+        });
+    }
+
+    /// Draws one-pixel lines through `points` in canvas coordinates, writing
+    /// the pixels shared by consecutive segments once.
+    pub fn draw_polyline(&mut self, points: &[Vec2], color: Vec4) {
+        self.draw_path(points, false, color);
+    }
+
+    /// Like `draw_polyline`, closing the outline back to the first point.
+    pub fn draw_polygon(&mut self, points: &[Vec2], color: Vec4) {
+        self.draw_path(points, true, color);
+    }
+
+    /// Draws the quadratic Bézier curve from `p0` to `p2` with control point
+    /// `p1`, in canvas coordinates, flattened to `bezier::DEFAULT_TOLERANCE`.
+    pub fn draw_quadratic_bezier(&mut self, p0: Vec2, p1: Vec2, p2: Vec2, color: Vec4) {
+        let mut points = vec![p0];
+        bezier::flatten_quadratic(p0, p1, p2, bezier::DEFAULT_TOLERANCE, &mut points);
+        self.draw_polyline(&points, color);
+    }
+
+    /// Draws the cubic Bézier curve from `p0` to `p3` with control points `p1`
+    /// and `p2`, in canvas coordinates, flattened to `bezier::DEFAULT_TOLERANCE`.
+    pub fn draw_cubic_bezier(&mut self, p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2, color: Vec4) {
+        let mut points = vec![p0];
+        bezier::flatten_cubic(p0, p1, p2, p3, bezier::DEFAULT_TOLERANCE, &mut points);
+        self.draw_polyline(&points, color);
+    }
+
+    /// Strokes the polyline through `points`, in canvas coordinates, as
+    /// filled polygons following `style`'s width, joins, caps and dashes.
+    /// Overlapping parts of the stroke are written once.
+    pub fn stroke_polyline(&mut self, points: &[Vec2], style: &StrokeStyle, color: Vec4) {
+        let outlines = stroke::stroke_outlines(points, false, style);
+        self.fill_contours(&outlines, FillRule::NonZero, color);
+    }
+
+    /// Like `stroke_polyline`, closing the outline back to the first point
+    /// with a join instead of caps.
+    pub fn stroke_polygon(&mut self, points: &[Vec2], style: &StrokeStyle, color: Vec4) {
+        let outlines = stroke::stroke_outlines(points, true, style);
+        self.fill_contours(&outlines, FillRule::NonZero, color);
+    }
+
+    /// Bresenham lines between the pixels containing `points`. Each segment
+    /// skips its first pixel, drawn by the previous one, and a closing
+    /// segment also skips the pixel the path started on.
+    fn draw_path(&mut self, points: &[Vec2], closed: bool, color: Vec4) {
+        let pixels: Vec<(i32, i32)> = points
+            .iter()
+            .map(|point| (point.x.floor() as i32, point.y.floor() as i32))
+            .collect();
+        let Some(&first) = pixels.first() else {
+            return;
+        };
+        if let [(x, y)] = pixels[..]
+            && let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y))
+        {
+            self.set_pixel(x, y, color);
+        }
+
+        // Two points would close over the same line again
+        let closing = (closed && pixels.len() > 2).then(|| (pixels[pixels.len() - 1], first));
+        let segments = pixels.windows(2).map(|pair| (pair[0], pair[1]));
+        let width = self.canvas_size.0;
+        let blend_mode = self.blend_mode;
+        let framebuffer = &mut self.framebuffer;
+        for (index, (start, end)) in segments.chain(closing).enumerate() {
+            let is_closing = index == pixels.len() - 1;
+            line::bresenham(start, end, self.canvas_size, |x, y| {
+                let pixel = (x as i32, y as i32);
+                if (index > 0 && pixel == start) || (is_closing && pixel == end) {
+                    return;
+                }
+                blend_mode.write(&mut framebuffer[pixel_index(width, x, y)], color);
+            });
+        }
     }
 
-    pub fn draw_line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Vec4) {
-        // Simple Bresenham-like line drawing for testing debugging
-        let steep = (x1 as i32 - x2 as i32).abs() < (y1 as i32 - y2 as i32).abs();
-        let (mut start_x, mut start_y, mut end_x, mut end_y) = (x1, y1, x2, y2);
-        if steep {
-            std::mem::swap(&mut start_x, &mut start_y);
-            std::mem::swap(&mut end_x, &mut end_y);
-        }
-        if start_x > end_x {
-            std::mem::swap(&mut start_x, &mut end_x);
-            std::mem::swap(&mut start_y, &mut end_y);
-        }
-
-        let dx = end_x - start_x;
-        let dy = if end_y > start_y { end_y - start_y } else { start_y - end_y };
-        
-        for x in start_x..=end_x {
-            let progress = if dx > 0 { (x - start_x) as f32 / dx as f32 } else { 0.0 };
-            let y = if end_y > start_y {
-                (start_y as f32 + dy as f32 * progress).round() as u32
-            } else {
-                (start_y as f32 - dy as f32 * progress).round() as u32
-            };
-
-            // Debug variables that should be visible in debugger
-            let debug_x = x;
-            let debug_y = y;
-            let debug_progress = progress;
-            let debug_color = color;
-            
-            println!("Drawing pixel at ({}, {}) with progress {:.2}", debug_x, debug_y, debug_progress);
-
-            if steep {
-                self.set_pixel(debug_y, debug_x, debug_color);
-            } else {
-                self.set_pixel(debug_x, debug_y, debug_color);
+    /// Fills a triangle whose vertices carry a depth in `[0, 1]` as the third
+    /// component. Depth is interpolated across the triangle and each pixel is
+    /// tested against the depth buffer using the current depth function.
+    pub fn fill_triangle_depth(
+        &mut self,
+        a: (f32, f32, f32),
+        b: (f32, f32, f32),
+        c: (f32, f32, f32),
+        color: Vec4,
+    ) {
+        let width = self.canvas_size.0;
+        let bounds = PixelRect::new(0, 0, width, self.canvas_size.1);
+        let (depth_func, depth_write) = (self.depth_func, self.depth_write);
+        let blend_mode = self.blend_mode;
+        let pick_id = self.current_pick_id();
+        let framebuffer = &mut self.framebuffer;
+        let depth_buffer = &mut self.depth_buffer;
+        let mut pick_buffer = self.pick_buffer.as_deref_mut();
+
+        let vertices = [(a.0, a.1), (b.0, b.1), (c.0, c.1)];
+        rasterize_triangle(vertices, bounds, |x, y, weights| {
+            let index = pixel_index(width, x, y);
+            let depth = weights[0] * a.2 + weights[1] * b.2 + weights[2] * c.2;
+
+            if depth_func.passes(depth, depth_buffer[index]) {
+                blend_mode.write(&mut framebuffer[index], color);
+                if depth_write {
+                    depth_buffer[index] = depth;
+                }
+                if let Some(pick_buffer) = pick_buffer.as_deref_mut() {
+                    pick_buffer[index] = pick_id;
+                }
             }
+        });
+    }
+
+    /// ID recorded by single-triangle draws, which are primitive 0 of their call.
+    fn current_pick_id(&self) -> Option<PickId> {
+        Some(PickId {
+            object: self.pick_object,
+            primitive: 0,
+        })
+    }
+
+    pub fn cull_mode(&self) -> CullMode {
+        self.cull_mode
+    }
+
+    pub fn set_cull_mode(&mut self, cull_mode: CullMode) {
+        self.cull_mode = cull_mode;
+    }
+
+    pub fn clip_planes(&self) -> &[Vec4] {
+        &self.clip_planes
+    }
+
+    /// Sets user clip planes as clip-space plane equations: geometry where
+    /// `plane.dot(position) < 0` is clipped away, on top of the view frustum.
+    pub fn set_clip_planes(&mut self, planes: Vec<Vec4>) {
+        self.clip_planes = planes;
+    }
+
+    /// Draws a line between two clip-space positions, clipped against the view
+    /// frustum and user clip planes before projection.
+    pub fn draw_clip_space_line(&mut self, a: Vec4, b: Vec4, color: Vec4) {
+        let Some((a, b)) = clip::clip_line(a, b, &FRUSTUM_PLANES)
+            .and_then(|(a, b)| clip::clip_line(a, b, &self.clip_planes))
+        else {
+            return;
+        };
+        if a.w() <= 0.0 || b.w() <= 0.0 {
+            return;
+        }
+
+        let start = self.viewport_transform(a);
+        let end = self.viewport_transform(b);
+        match self.line_mode {
+            LineMode::Aliased => self.draw_line(
+                start.x.floor() as i32,
+                start.y.floor() as i32,
+                end.x.floor() as i32,
+                end.y.floor() as i32,
+                color,
+            ),
+            LineMode::AntiAliased => self.draw_line_aa(start.x, start.y, end.x, end.y, color),
         }
     }
 
+    /// Number of worker threads `draw_mesh` shades tiles with; 1 shades on
+    /// the calling thread.
+    pub fn render_threads(&self) -> usize {
+        self.workers.threads()
+    }
+
+    /// Replaces the worker threads, which start with the next `draw_mesh`.
+    pub fn set_render_threads(&mut self, threads: usize) {
+        if threads.max(1) != self.workers.threads() {
+            self.workers = WorkerPool::new(threads);
+        }
+    }
+
+    /// Draws every triangle of `mesh` through `shader`: the vertex stage maps
+    /// each corner to clip space, the triangle is rasterized with depth testing
+    /// and the fragment stage shades each covered pixel. Shading is split into
+    /// tiles across `render_threads` workers.
+    pub fn draw_mesh<S: Shader>(&mut self, mesh: &Mesh, shader: &S) {
+        let mut triangles = Vec::with_capacity(mesh.triangles.len());
+        for (index, triangle) in mesh.triangles.iter().enumerate() {
+            let positions = mesh.triangle_positions(triangle);
+            let face_normal = (positions[1] - positions[0])
+                .cross(positions[2] - positions[0])
+                .normalize();
+
+            let vertices = std::array::from_fn(|corner| {
+                let vertex = triangle[corner];
+                shader.vertex(&VertexInput {
+                    position: positions[corner],
+                    tex_coord: vertex.tex_coord.map_or(Vec2::ZERO, |i| mesh.tex_coords[i]),
+                    normal: vertex.normal.map_or(face_normal, |i| mesh.normals[i]),
+                    face_normal,
+                    triangle: index,
+                    corner,
+                })
+            });
+
+            self.setup_triangle(&vertices, index as u32, &mut triangles);
+        }
+
+        let state = RasterState {
+            canvas_size: self.canvas_size,
+            depth_func: self.depth_func,
+            depth_write: self.depth_write,
+            blend_mode: self.blend_mode,
+            pick_object: self.pick_object,
+        };
+        pipeline::shade_triangles(
+            &triangles,
+            shader,
+            state,
+            &mut self.framebuffer,
+            &mut self.depth_buffer,
+            self.pick_buffer.as_deref_mut(),
+            &mut self.workers,
+        );
+    }
+
+    /// Maps a clip-space position to canvas coordinates plus a `[0, 1]` depth,
+    /// keeping `1 / w` for perspective-correct interpolation.
+    fn viewport_transform(&self, clip: Vec4) -> ScreenVertex {
+        let inverse_w = 1.0 / clip.w();
+        let ndc = clip.xyz() * inverse_w;
+        ScreenVertex {
+            x: (ndc.x + 1.0) * 0.5 * self.canvas_size.0 as f32,
+            y: (ndc.y + 1.0) * 0.5 * self.canvas_size.1 as f32,
+            depth: (ndc.z + 1.0) * 0.5,
+            inverse_w,
+        }
+    }
+
+    /// Clips a clip-space triangle against the frustum and user clip planes,
+    /// then projects, culls and queues what remains for shading.
+    fn setup_triangle<V: Varyings>(
+        &self,
+        vertices: &[VertexOutput<V>; 3],
+        primitive: u32,
+        triangles: &mut Vec<SetupTriangle<V>>,
+    ) {
+        let inside = vertices.iter().all(|vertex| {
+            clip::is_inside(vertex.position, &FRUSTUM_PLANES)
+                && clip::is_inside(vertex.position, &self.clip_planes)
+        });
+        if inside {
+            self.setup_clipped_triangle(vertices, primitive, triangles);
+            return;
+        }
+
+        let mut polygon = vertices.to_vec();
+        let mut scratch = Vec::with_capacity(polygon.len() + 2);
+        clip::clip_polygon(&mut polygon, &mut scratch, &FRUSTUM_PLANES);
+        clip::clip_polygon(&mut polygon, &mut scratch, &self.clip_planes);
+
+        // The clipped polygon is convex, so a fan preserves its winding
+        for i in 1..polygon.len().saturating_sub(1) {
+            let triangle = [polygon[0], polygon[i], polygon[i + 1]];
+            self.setup_clipped_triangle(&triangle, primitive, triangles);
+        }
+    }
+
+    fn setup_clipped_triangle<V: Varyings>(
+        &self,
+        vertices: &[VertexOutput<V>; 3],
+        primitive: u32,
+        triangles: &mut Vec<SetupTriangle<V>>,
+    ) {
+        // Only degenerate clipped vertices can still sit at the eye
+        if vertices.iter().any(|vertex| vertex.position.w() <= 0.0) {
+            return;
+        }
+
+        let screen = vertices.map(|vertex| self.viewport_transform(vertex.position));
+        let signed_area = (screen[1].x - screen[0].x) * (screen[2].y - screen[0].y)
+            - (screen[2].x - screen[0].x) * (screen[1].y - screen[0].y);
+        let culled = match self.cull_mode {
+            CullMode::None => false,
+            CullMode::Back => signed_area <= 0.0,
+            CullMode::Front => signed_area >= 0.0,
+        };
+        if culled {
+            return;
+        }
+
+        let varyings = vertices.map(|vertex| vertex.varyings);
+        triangles.extend(SetupTriangle::new(
+            screen,
+            varyings,
+            primitive,
+            self.canvas_size,
+        ));
+    }
+
     pub fn render_frame(&mut self) {
         // Clear with black background
         self.clear(Vec4::black());
 
-        // All coordinates are in 64x64 canvas space
-        let (ax, ay) = (7, 3);
-        let (bx, by) = (12, 37);
-        let (cx, cy) = (62, 53);
+        // Coordinates are laid out on a 64x64 grid and scaled to the canvas
+        let (width, height) = (self.canvas_size.0 as i64, self.canvas_size.1 as i64);
+        let scale = |x: i64, y: i64| ((x * width / 64) as i32, (y * height / 64) as i32);
+        let (ax, ay) = scale(7, 3);
+        let (bx, by) = scale(12, 37);
+        let (cx, cy) = scale(62, 53);
 
+        self.fill_triangle(
+            (ax as f32, ay as f32),
+            (bx as f32, by as f32),
+            (cx as f32, cy as f32),
+            Vec4::red(),
+        );
         self.draw_line(ax, ay, bx, by, Vec4::blue());
     }
 
@@ -153,23 +867,108 @@ impl SoftwareCanvas {
         self.canvas_size.1
     }
 
+    /// Changes the logical resolution, reallocating the framebuffer and depth
+    /// buffer. Their contents are cleared to black and `DEPTH_CLEAR_VALUE`.
+    pub fn set_canvas_size(&mut self, width: u32, height: u32) {
+        self.canvas_size = (width, height);
+        let len = buffer_len(self.canvas_size);
+        self.framebuffer = vec![Vec4::black().to_argb(); len];
+        self.depth_buffer = vec![DEPTH_CLEAR_VALUE; len];
+        if let Some(pick_buffer) = &mut self.pick_buffer {
+            *pick_buffer = vec![None; len];
+        }
+    }
+
+    pub fn sizing(&self) -> CanvasSizing {
+        self.sizing
+    }
+
+    /// Sets whether `resize_to_window` changes the canvas resolution.
+    pub fn set_sizing(&mut self, sizing: CanvasSizing) {
+        self.sizing = sizing;
+    }
+
+    /// Applies the sizing policy for a window of `window_size` pixels,
+    /// reallocating the buffers if the resolution changes. Returns whether it did.
+    pub fn resize_to_window(&mut self, window_size: (u32, u32)) -> bool {
+        let CanvasSizing::FollowWindow(fraction) = self.sizing else {
+            return false;
+        };
+        let scaled = |size: u32| ((size as f32 * fraction).round() as u32).max(1);
+        let canvas_size = (scaled(window_size.0), scaled(window_size.1));
+        if window_size.0 == 0 || window_size.1 == 0 || canvas_size == self.canvas_size {
+            return false;
+        }
+
+        self.set_canvas_size(canvas_size.0, canvas_size.1);
+        true
+    }
+
+    /// Size of the window surface the canvas was last presented to.
+    pub fn window_size(&self) -> (u32, u32) {
+        self.window_size
+    }
+
+    /// Maps a window position in physical pixels (origin top-left, as winit
+    /// reports the cursor) to canvas coordinates (origin bottom-left), where
+    /// pixel `(x, y)` spans `[x, x + 1) x [y, y + 1)`. Honors the scale mode;
+    /// returns `None` over letterbox bars or outside the window.
+    pub fn window_to_canvas(&self, x: f32, y: f32) -> Option<Vec2> {
+        let viewport = self.scale_mode.viewport(self.canvas_size, self.window_size);
+        if viewport.is_empty() {
+            return None;
+        }
+
+        let u = (x - viewport.x as f32) / viewport.width as f32;
+        let v = (y - viewport.y as f32) / viewport.height as f32;
+        if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+            return None;
+        }
+
+        // Flip Y coordinate: the window is top-down, the canvas bottom-up.
+        // The top edge of the window belongs to the last canvas row.
+        let height = self.canvas_size.1 as f32;
+        Some(Vec2::new(
+            u * self.canvas_size.0 as f32,
+            ((1.0 - v) * height).min(height.next_down()),
+        ))
+    }
+
+    pub fn scale_mode(&self) -> ScaleMode {
+        self.scale_mode
+    }
+
+    /// Sets how `present_frame` fits the canvas into the window.
+    pub fn set_scale_mode(&mut self, scale_mode: ScaleMode) {
+        self.scale_mode = scale_mode;
+    }
+
+    pub fn scale_filter(&self) -> ScaleFilter {
+        self.scale_filter
+    }
+
+    pub fn set_scale_filter(&mut self, scale_filter: ScaleFilter) {
+        self.scale_filter = scale_filter;
+    }
+
     pub fn ensure_surface_size(&mut self, window: &Window) -> Result<()> {
         if let Some(surface) = &mut self.surface {
             let size = window.inner_size();
             let current_size = (size.width, size.height);
 
             // Always resize on macOS to ensure proper surface initialization
-            if self.current_surface_size != Some(current_size) || cfg!(target_os = "macos") {
-                if size.width > 0 && size.height > 0 {
-                    surface
-                        .resize(
-                            NonZeroU32::new(size.width).expect("Window width cannot be zero"),
-                            NonZeroU32::new(size.height).expect("Window height cannot be zero"),
-                        )
-                        .expect("Failed to resize surface");
-                    self.current_surface_size = Some(current_size);
-                    self.window_size = current_size;
-                }
+            if (self.current_surface_size != Some(current_size) || cfg!(target_os = "macos"))
+                && size.width > 0
+                && size.height > 0
+            {
+                surface
+                    .resize(
+                        NonZeroU32::new(size.width).expect("Window width cannot be zero"),
+                        NonZeroU32::new(size.height).expect("Window height cannot be zero"),
+                    )
+                    .expect("Failed to resize surface");
+                self.current_surface_size = Some(current_size);
+                self.window_size = current_size;
             }
         }
         Ok(())
@@ -177,9 +976,31 @@ impl SoftwareCanvas {
 
     pub fn present_frame(&mut self) -> Result<()> {
         if let Some(surface) = &mut self.surface {
-            let buffer = surface.buffer_mut().expect("Failed to get surface buffer");
+            let mut buffer = surface.buffer_mut().expect("Failed to get surface buffer");
+            if buffer.len() == buffer_len(self.window_size) {
+                blit::present(
+                    &self.framebuffer,
+                    self.canvas_size,
+                    &mut buffer,
+                    self.window_size,
+                    self.scale_mode,
+                    self.scale_filter,
+                );
+            }
+
             buffer.present().expect("Failed to present buffer");
         }
         Ok(())
     }
 }
+
+/// Number of pixels in a buffer of `size`, computed in `usize` so large sizes
+/// can't overflow `u32`.
+fn buffer_len(size: (u32, u32)) -> usize {
+    size.0 as usize * size.1 as usize
+}
+
+/// Index of pixel `(x, y)` in a buffer `width` pixels wide.
+fn pixel_index(width: u32, x: u32, y: u32) -> usize {
+    y as usize * width as usize + x as usize
+}
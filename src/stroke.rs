@@ -0,0 +1,597 @@
+//! Thick strokes: polylines widened into polygons with joins, caps and
+//! dashes, which the canvas fills like any other polygon.
+
+use crate::vec2::Vec2;
+use std::f32::consts::PI;
+
+/// Miter limit used by `StrokeStyle::new`, the same default as SVG.
+pub const DEFAULT_MITER_LIMIT: f32 = 4.0;
+
+/// Maximum distance, in pixels, between round caps or joins and the
+/// polygons approximating them. Finer than Bézier flattening since
+/// small round caps are mostly boundary.
+const CIRCLE_TOLERANCE: f32 = 0.1;
+
+/// Shortest dash period, in pixels, that is drawn dashed; finer patterns
+/// stroke solid.
+pub const MIN_DASH_PERIOD: f32 = 1.0 / 16.0;
+
+/// Most dash pattern entries a single segment is split into, bounding the
+/// outlines a long segment with a fine pattern produces.
+const MAX_DASHES_PER_SEGMENT: u64 = 4096;
+
+/// How two segments of a stroke are connected on the outside of a turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineJoin {
+    /// Extend the outer edges until they meet, falling back to `Bevel` for
+    /// sharp turns beyond the miter limit
+    #[default]
+    Miter,
+    /// Round off the corner with a circle arc
+    Round,
+    /// Cut the corner off straight between the outer edges
+    Bevel,
+}
+
+/// How the open ends of a stroke, and of every dash, are finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineCap {
+    /// End flat at the endpoint
+    #[default]
+    Butt,
+    /// Extend by a half circle around the endpoint
+    Round,
+    /// Extend flat by half the width past the endpoint
+    Square,
+}
+
+/// Width, joins, caps and dashing of a stroke.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrokeStyle {
+    /// Stroke width in pixels
+    pub width: f32,
+    pub join: LineJoin,
+    /// Longest miter allowed, as a multiple of the width
+    pub miter_limit: f32,
+    pub cap: LineCap,
+    /// Alternating dash and gap lengths in pixels, repeated along the stroke.
+    /// An odd number of lengths is repeated twice; empty draws a solid stroke.
+    pub dash: Vec<f32>,
+    /// Distance into the dash pattern the stroke starts at
+    pub dash_offset: f32,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl StrokeStyle {
+    /// Solid stroke `width` pixels wide with miter joins and butt caps.
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            join: LineJoin::default(),
+            miter_limit: DEFAULT_MITER_LIMIT,
+            cap: LineCap::default(),
+            dash: Vec::new(),
+            dash_offset: 0.0,
+        }
+    }
+
+    pub fn with_join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    pub fn with_miter_limit(mut self, miter_limit: f32) -> Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    pub fn with_cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn with_dash(mut self, dash: &[f32], offset: f32) -> Self {
+        self.dash = dash.to_vec();
+        self.dash_offset = offset;
+        self
+    }
+
+    /// The dash pattern with an even number of lengths, or `None` for a solid
+    /// stroke. Patterns with negative or non-finite lengths, or whose lengths
+    /// add up to less than `MIN_DASH_PERIOD`, are ignored.
+    fn dash_pattern(&self) -> Option<Vec<f32>> {
+        let valid = self
+            .dash
+            .iter()
+            .all(|&length| length >= 0.0 && length.is_finite());
+        if !valid || self.dash.iter().sum::<f32>() < MIN_DASH_PERIOD {
+            return None;
+        }
+
+        let mut pattern = self.dash.clone();
+        if pattern.len() % 2 == 1 {
+            pattern.extend_from_within(..);
+        }
+        Some(pattern)
+    }
+}
+
+/// Outlines whose union is the stroke of the polyline through `points`,
+/// closed back to the first point if `closed`.
+///
+/// Every outline is a convex polygon wound counter-clockwise, so filling them
+/// together with `FillRule::NonZero` covers the stroke without writing any
+/// pixel twice, however the pieces overlap.
+pub fn stroke_outlines(points: &[Vec2], closed: bool, style: &StrokeStyle) -> Vec<Vec<Vec2>> {
+    let mut builder = OutlineBuilder {
+        style,
+        half_width: style.width * 0.5,
+        outlines: Vec::new(),
+    };
+    if !(builder.half_width > 0.0 && builder.half_width.is_finite()) {
+        return builder.outlines;
+    }
+
+    let mut points: Vec<Vec2> = points
+        .iter()
+        .copied()
+        .filter(|point| point.is_finite())
+        .collect();
+    points.dedup();
+    if closed && points.len() > 2 && points.first() == points.last() {
+        points.pop();
+    }
+
+    match style.dash_pattern() {
+        Some(pattern) => {
+            if closed && points.len() > 2 {
+                points.push(points[0]);
+            }
+            for dash in split_dashes(&points, &pattern, style.dash_offset) {
+                builder.open(&dash);
+            }
+        }
+        None if closed && points.len() > 2 => builder.closed(&points),
+        None => builder.open(&points),
+    }
+    builder.outlines
+}
+
+/// Splits the polyline through `points` into the pieces the dash `pattern`
+/// keeps, starting `offset` pixels into the pattern.
+///
+/// Dash boundaries are computed from their index in the pattern rather than
+/// by summing lengths, so rounding can't stall on short dashes far along the
+/// stroke. A segment that would need more than `MAX_DASHES_PER_SEGMENT`
+/// entries is kept whole instead.
+fn split_dashes(points: &[Vec2], pattern: &[f32], offset: f32) -> Vec<Vec<Vec2>> {
+    let mut dashes = Vec::new();
+    let Some(&first) = points.first() else {
+        return dashes;
+    };
+
+    // Where each pattern entry ends within a period
+    let ends: Vec<f64> = pattern
+        .iter()
+        .scan(0.0, |sum, &length| {
+            *sum += length as f64;
+            Some(*sum)
+        })
+        .collect();
+    let count = ends.len() as u64;
+    let period = ends[ends.len() - 1];
+    let phase = (offset as f64).rem_euclid(period);
+
+    // Entry `k` counts from the start of the period the stroke starts in, and
+    // ends `boundary(k)` pixels along the stroke
+    let boundary = |k: u64| (k / count) as f64 * period + ends[(k % count) as usize] - phase;
+    let entry_at = |distance: f64| {
+        let periods = ((distance + phase) / period).floor();
+        let within = distance + phase - periods * period;
+        let index = ends
+            .iter()
+            .position(|&end| end > within)
+            .unwrap_or(ends.len());
+        periods as u64 * count + index as u64
+    };
+    // Patterns have an even number of entries, so every period starts with a dash
+    let is_dash = |k: u64| k.is_multiple_of(2);
+
+    let mut entry = entry_at(0.0);
+    let mut dash = is_dash(entry).then(|| vec![first]);
+    let mut distance = 0.0;
+
+    for segment in points.windows(2) {
+        let (start, end) = (segment[0], segment[1]);
+        let length = (end - start).length() as f64;
+        let segment_end = distance + length;
+
+        let crossings = (segment_end - boundary(entry)) / period * count as f64;
+        if crossings > MAX_DASHES_PER_SEGMENT as f64 {
+            // Too fine to split: keep the whole segment and pick the pattern
+            // up again where it ends
+            dash.get_or_insert_with(|| vec![start]).push(end);
+            entry = entry_at(segment_end);
+            if !is_dash(entry)
+                && let Some(points) = dash.take()
+            {
+                dashes.push(points);
+            }
+            distance = segment_end;
+            continue;
+        }
+
+        // Pattern entries that end within this segment
+        while boundary(entry) < segment_end {
+            let point = start.lerp(end, ((boundary(entry) - distance) / length) as f32);
+            match dash.take() {
+                Some(mut points) => {
+                    points.push(point);
+                    dashes.push(points);
+                }
+                None => dash = Some(vec![point]),
+            }
+            entry += 1;
+        }
+
+        if let Some(points) = &mut dash {
+            points.push(end);
+        }
+        distance = segment_end;
+    }
+
+    if let Some(points) = dash
+        && points.len() > 1
+    {
+        dashes.push(points);
+    }
+    dashes
+}
+
+struct OutlineBuilder<'a> {
+    style: &'a StrokeStyle,
+    half_width: f32,
+    outlines: Vec<Vec<Vec2>>,
+}
+
+impl OutlineBuilder<'_> {
+    /// Segments, joins at the inner points and caps at both ends.
+    fn open(&mut self, points: &[Vec2]) {
+        let mut points = points.to_vec();
+        points.dedup();
+
+        match points[..] {
+            [] => {}
+            [point] => self.dot(point),
+            [.., second_last, last] => {
+                for segment in points.windows(2) {
+                    self.segment(segment[0], segment[1]);
+                }
+                for corner in points.windows(3) {
+                    self.join(corner[0], corner[1], corner[2]);
+                }
+                self.cap(points[0], (points[0] - points[1]).normalize());
+                self.cap(last, (last - second_last).normalize());
+            }
+        }
+    }
+
+    /// Segments between consecutive points and back to the first, with a
+    /// join at every point.
+    fn closed(&mut self, points: &[Vec2]) {
+        let count = points.len();
+        for index in 0..count {
+            let (previous, point) = (points[(index + count - 1) % count], points[index]);
+            self.segment(point, points[(index + 1) % count]);
+            self.join(previous, point, points[(index + 1) % count]);
+        }
+    }
+
+    /// Rectangle `width` wide around the segment `start -> end`.
+    fn segment(&mut self, start: Vec2, end: Vec2) {
+        let normal = (end - start).normalize().perp() * self.half_width;
+        self.outlines.push(vec![
+            start - normal,
+            end - normal,
+            end + normal,
+            start + normal,
+        ]);
+    }
+
+    /// Fills the gap on the outside of the turn at `point` between the
+    /// segments from `previous` and to `next`.
+    fn join(&mut self, previous: Vec2, point: Vec2, next: Vec2) {
+        let incoming = (point - previous).normalize();
+        let outgoing = (next - point).normalize();
+        let turn = incoming.cross(outgoing);
+        if turn.abs() < 1e-6 && incoming.dot(outgoing) > 0.0 {
+            return;
+        }
+
+        if self.style.join == LineJoin::Round {
+            self.outlines.push(circle(point, self.half_width));
+            return;
+        }
+
+        // The outside of a left turn is on the right, and vice versa
+        let side = if turn > 0.0 { -1.0 } else { 1.0 };
+        let (incoming_normal, outgoing_normal) = (incoming.perp() * side, outgoing.perp() * side);
+        let incoming_corner = point + incoming_normal * self.half_width;
+        let outgoing_corner = point + outgoing_normal * self.half_width;
+
+        if self.style.join == LineJoin::Miter {
+            // The miter length relative to the width is 1 / cos of half the
+            // angle between the normals
+            let bisector = (incoming_normal + outgoing_normal).normalize();
+            let cos_half_angle = bisector.dot(incoming_normal);
+            if cos_half_angle > 0.0 && cos_half_angle * self.style.miter_limit >= 1.0 {
+                let tip = point + bisector * (self.half_width / cos_half_angle);
+                self.push_convex(vec![point, incoming_corner, tip, outgoing_corner]);
+                return;
+            }
+        }
+        self.push_convex(vec![point, incoming_corner, outgoing_corner]);
+    }
+
+    /// Finishes the open end at `point`, where the stroke points in `direction`.
+    fn cap(&mut self, point: Vec2, direction: Vec2) {
+        match self.style.cap {
+            LineCap::Butt => {}
+            LineCap::Round => self.outlines.push(circle(point, self.half_width)),
+            LineCap::Square => self.segment(point, point + direction * self.half_width),
+        }
+    }
+
+    /// A stroke of a single point, which only has caps. Square caps have no
+    /// direction to follow, so they are axis aligned.
+    fn dot(&mut self, point: Vec2) {
+        match self.style.cap {
+            LineCap::Butt => {}
+            LineCap::Round => self.outlines.push(circle(point, self.half_width)),
+            LineCap::Square => self.segment(
+                point - Vec2::new(self.half_width, 0.0),
+                point + Vec2::new(self.half_width, 0.0),
+            ),
+        }
+    }
+
+    /// Adds a convex polygon given in either winding order.
+    fn push_convex(&mut self, mut polygon: Vec<Vec2>) {
+        let twice_area: f32 = polygon
+            .iter()
+            .zip(polygon.iter().cycle().skip(1))
+            .map(|(&a, &b)| a.cross(b))
+            .sum();
+        if twice_area < 0.0 {
+            polygon.reverse();
+        }
+        self.outlines.push(polygon);
+    }
+}
+
+/// Counter-clockwise polygon within `CIRCLE_TOLERANCE` of the circle.
+fn circle(center: Vec2, radius: f32) -> Vec<Vec2> {
+    // A chord of angle `step` lies `radius * (1 - cos(step / 2))` inside the circle
+    let ratio = 1.0 - CIRCLE_TOLERANCE / radius;
+    let segments = if ratio > 0.0 {
+        (PI / ratio.acos()).ceil().clamp(8.0, 512.0) as u32
+    } else {
+        8
+    };
+
+    // Push the vertices out so the polygon straddles the circle instead of
+    // lying inside it, which would visibly shrink small caps and joins
+    let step = 2.0 * PI / segments as f32;
+    let vertex_radius = 2.0 * radius / (1.0 + (step * 0.5).cos());
+    (0..segments)
+        .map(|index| {
+            let (sin, cos) = (index as f32 * step).sin_cos();
+            center + Vec2::new(cos, sin) * vertex_radius
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::polygon_spans;
+    use crate::software_canvas::FillRule;
+    use std::collections::HashSet;
+
+    /// Dashes along a horizontal line from 0 to `length`, as x ranges.
+    fn dash_ranges(length: f32, dash: &[f32], offset: f32) -> Vec<(f32, f32)> {
+        let style = StrokeStyle::new(1.0).with_dash(dash, offset);
+        let pattern = style.dash_pattern().unwrap();
+        let points = [Vec2::new(0.0, 0.0), Vec2::new(length, 0.0)];
+        split_dashes(&points, &pattern, style.dash_offset)
+            .iter()
+            .map(|dash| {
+                assert_eq!(dash.len(), 2);
+                (dash[0].x, dash[1].x)
+            })
+            .collect()
+    }
+
+    fn assert_ranges(actual: &[(f32, f32)], expected: &[(f32, f32)]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (&(a0, a1), &(e0, e1)) in actual.iter().zip(expected) {
+            assert!(
+                (a0 - e0).abs() < 1e-4 && (a1 - e1).abs() < 1e-4,
+                "{actual:?}"
+            );
+        }
+    }
+
+    /// Pixels covered by the stroke, checking its outlines are counter-clockwise.
+    fn stroke_pixels(points: &[Vec2], closed: bool, style: &StrokeStyle) -> HashSet<(u32, u32)> {
+        let outlines = stroke_outlines(points, closed, style);
+        for outline in &outlines {
+            assert!(outline.iter().all(|point| point.is_finite()), "{outline:?}");
+            let twice_area: f32 = outline
+                .iter()
+                .zip(outline.iter().cycle().skip(1))
+                .map(|(&a, &b)| a.cross(b))
+                .sum();
+            assert!(twice_area >= 0.0, "{outline:?}");
+        }
+
+        let mut pixels = HashSet::new();
+        polygon_spans(&outlines, FillRule::NonZero, (32, 32), |y, x0, x1| {
+            pixels.extend((x0..x1).map(|x| (x, y)));
+        });
+        pixels
+    }
+
+    #[test]
+    fn dashes_start_and_end_on_pattern_boundaries() {
+        assert_ranges(
+            &dash_ranges(10.0, &[2.0, 1.0], 0.0),
+            &[(0.0, 2.0), (3.0, 5.0), (6.0, 8.0), (9.0, 10.0)],
+        );
+        // An odd pattern repeats with dashes and gaps swapped
+        let expected = [
+            (0.0, 1.0),
+            (3.0, 6.0),
+            (7.0, 9.0),
+            (12.0, 13.0),
+            (15.0, 18.0),
+            (19.0, 20.0),
+        ];
+        assert_ranges(&dash_ranges(20.0, &[1.0, 2.0, 3.0], 0.0), &expected);
+
+        // Starting within a gap, and a negative offset a period earlier
+        let expected = [
+            (1.0, 4.0),
+            (5.0, 7.0),
+            (10.0, 11.0),
+            (13.0, 16.0),
+            (17.0, 19.0),
+        ];
+        assert_ranges(&dash_ranges(20.0, &[1.0, 2.0, 3.0], 2.0), &expected);
+        assert_ranges(&dash_ranges(20.0, &[1.0, 2.0, 3.0], -10.0), &expected);
+        // Starting within a dash
+        assert_ranges(
+            &dash_ranges(7.0, &[4.0, 2.0], 3.0),
+            &[(0.0, 1.0), (3.0, 7.0)],
+        );
+    }
+
+    #[test]
+    fn dashes_follow_corners() {
+        let points = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(4.0, 0.0),
+            Vec2::new(4.0, 4.0),
+        ];
+        let dashes = split_dashes(&points, &[6.0, 1.0], 0.0);
+        assert_eq!(
+            dashes,
+            [
+                vec![points[0], points[1], Vec2::new(4.0, 2.0)],
+                vec![Vec2::new(4.0, 3.0), points[2]],
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_dash_patterns_stroke_solid() {
+        for dash in [
+            &[][..],
+            &[0.0, 0.0],
+            &[0.01, 0.01],
+            &[2.0, -1.0],
+            &[2.0, f32::NAN],
+            &[f32::INFINITY],
+        ] {
+            assert_eq!(
+                StrokeStyle::new(1.0).with_dash(dash, 0.0).dash_pattern(),
+                None,
+                "{dash:?}"
+            );
+        }
+        let pattern = StrokeStyle::new(1.0)
+            .with_dash(&[MIN_DASH_PERIOD], 0.0)
+            .dash_pattern();
+        assert_eq!(pattern, Some(vec![MIN_DASH_PERIOD; 2]));
+    }
+
+    #[test]
+    fn miter_falls_back_to_bevel_beyond_the_limit() {
+        // Turns back by `180 - angle` degrees, whose miter is 1 / sin(angle / 2)
+        // times the width
+        let join = |angle: f32, miter_limit: f32| {
+            let (sin, cos) = (PI - angle.to_radians()).sin_cos();
+            let points = [
+                Vec2::new(0.0, 0.0),
+                Vec2::new(10.0, 0.0),
+                Vec2::new(10.0 + 10.0 * cos, 10.0 * sin),
+            ];
+            let style = StrokeStyle::new(2.0).with_miter_limit(miter_limit);
+            let outlines = stroke_outlines(&points, false, &style);
+            assert_eq!(outlines.len(), 3);
+            outlines[2].clone()
+        };
+
+        let miter = join(90.0, 1.5);
+        assert_eq!(miter.len(), 4);
+        let tip = miter
+            .iter()
+            .map(|&point| (point - Vec2::new(10.0, 0.0)).length());
+        assert!((tip.fold(0.0, f32::max) - 2f32.sqrt()).abs() < 1e-5);
+        assert_eq!(join(90.0, 1.4).len(), 3);
+
+        // The default limit of 4 is reached at about 29 degrees
+        assert_eq!(join(30.0, DEFAULT_MITER_LIMIT).len(), 4);
+        assert_eq!(join(28.0, DEFAULT_MITER_LIMIT).len(), 3);
+        assert_eq!(join(28.0, f32::INFINITY).len(), 4);
+    }
+
+    #[test]
+    fn reversals_produce_finite_outlines() {
+        let points = [
+            Vec2::new(4.0, 8.0),
+            Vec2::new(20.0, 8.0),
+            Vec2::new(4.0, 8.0),
+        ];
+        for join in [LineJoin::Miter, LineJoin::Round, LineJoin::Bevel] {
+            for cap in [LineCap::Butt, LineCap::Round, LineCap::Square] {
+                let style = StrokeStyle::new(4.0).with_join(join).with_cap(cap);
+                let pixels = stroke_pixels(&points, false, &style);
+                // The stroke covers the line and doesn't spike past the reversal
+                assert!((4..20).all(|x| pixels.contains(&(x, 7)) && pixels.contains(&(x, 8))));
+                assert!(pixels.iter().all(|&(x, y)| x < 23 && (5..11).contains(&y)));
+            }
+        }
+    }
+
+    #[test]
+    fn closed_paths_join_back_to_the_start() {
+        let square = [
+            Vec2::new(8.0, 8.0),
+            Vec2::new(24.0, 8.0),
+            Vec2::new(24.0, 24.0),
+            Vec2::new(8.0, 24.0),
+        ];
+        let style = StrokeStyle::new(4.0);
+        let closed = stroke_pixels(&square, true, &style);
+        let open = stroke_pixels(&square, false, &style);
+        // The closing side, and the miter at the first point
+        assert!(closed.contains(&(7, 16)) && !open.contains(&(7, 16)));
+        assert!(closed.contains(&(6, 6)) && !open.contains(&(6, 6)));
+
+        // Dashing continues around the closing side: the pattern below is
+        // on along the first and last sides, split where the path starts
+        let dashed = style.with_dash(&[32.0, 32.0], 16.0);
+        let pixels = stroke_pixels(&square, true, &dashed);
+        let solid = StrokeStyle::new(4.0);
+        let mut expected = stroke_pixels(&[square[3], square[0]], false, &solid);
+        expected.extend(stroke_pixels(&[square[0], square[1]], false, &solid));
+        assert_eq!(pixels, expected);
+        assert!(pixels.contains(&(7, 16)) && !pixels.contains(&(25, 16)));
+    }
+}
@@ -0,0 +1,312 @@
+use crate::image_io;
+use crate::vec2::Vec2;
+use crate::vec4::Vec4;
+use anyhow::{Result, bail};
+use std::path::Path;
+
+/// How texels are combined within a mip level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Filter {
+    Nearest,
+    #[default]
+    Bilinear,
+}
+
+/// How mip levels are selected by `sample_grad`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MipmapMode {
+    /// Always sample the base level
+    None,
+    /// Sample the single closest level
+    Nearest,
+    /// Blend the two closest levels (trilinear filtering with `Filter::Bilinear`)
+    #[default]
+    Linear,
+}
+
+/// How texture coordinates outside `[0, 1]` are mapped back onto the texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WrapMode {
+    #[default]
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl WrapMode {
+    fn apply(self, index: i64, size: u32) -> u32 {
+        let size = size as i64;
+        let wrapped = match self {
+            Self::Repeat => index.rem_euclid(size),
+            Self::Clamp => index.clamp(0, size - 1),
+            Self::Mirror => {
+                let period = index.rem_euclid(2 * size);
+                if period < size {
+                    period
+                } else {
+                    2 * size - 1 - period
+                }
+            }
+        };
+        wrapped as u32
+    }
+
+    /// Moves a texel coordinate into a small range that wraps to the same
+    /// texels, keeping its fraction, so huge coordinates don't saturate when
+    /// converted to integers. Non-finite coordinates become 0.
+    fn reduce(self, coordinate: f32, size: u32) -> f32 {
+        if !coordinate.is_finite() {
+            return 0.0;
+        }
+        let size = size as f32;
+        match self {
+            Self::Repeat | Self::Mirror => coordinate.rem_euclid(2.0 * size),
+            Self::Clamp => coordinate.clamp(-1.0, size + 1.0),
+        }
+    }
+}
+
+/// One mip level, texels stored bottom row first like the canvas, so that
+/// `v = 0` is the bottom edge of the image.
+#[derive(Debug, Clone)]
+struct MipLevel {
+    width: u32,
+    height: u32,
+    texels: Vec<Vec4>,
+}
+
+impl MipLevel {
+    fn texel(&self, x: i64, y: i64, wrap: WrapMode) -> Vec4 {
+        let x = wrap.apply(x, self.width);
+        let y = wrap.apply(y, self.height);
+        self.texels[y as usize * self.width as usize + x as usize]
+    }
+
+    fn sample(&self, uv: Vec2, filter: Filter, wrap: WrapMode) -> Vec4 {
+        let x = uv.x * self.width as f32;
+        let y = uv.y * self.height as f32;
+
+        match filter {
+            Filter::Nearest => {
+                let x = wrap.reduce(x, self.width).floor();
+                let y = wrap.reduce(y, self.height).floor();
+                self.texel(x as i64, y as i64, wrap)
+            }
+            Filter::Bilinear => {
+                // Texel centers sit at half-integer coordinates
+                let x = wrap.reduce(x - 0.5, self.width);
+                let y = wrap.reduce(y - 0.5, self.height);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                let bottom = self
+                    .texel(x0, y0, wrap)
+                    .lerp(self.texel(x0 + 1, y0, wrap), fx);
+                let top = self
+                    .texel(x0, y0 + 1, wrap)
+                    .lerp(self.texel(x0 + 1, y0 + 1, wrap), fx);
+                bottom.lerp(top, fy)
+            }
+        }
+    }
+
+    /// The next smaller level, averaging 2x2 blocks. The last column or row of
+    /// an odd size doesn't fill a block and is dropped; a size of 1 repeats.
+    fn downsample(&self) -> Self {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut texels = Vec::with_capacity(width as usize * height as usize);
+
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                let sum = self.texel(2 * x, 2 * y, WrapMode::Clamp)
+                    + self.texel(2 * x + 1, 2 * y, WrapMode::Clamp)
+                    + self.texel(2 * x, 2 * y + 1, WrapMode::Clamp)
+                    + self.texel(2 * x + 1, 2 * y + 1, WrapMode::Clamp);
+                texels.push(sum * 0.25);
+            }
+        }
+
+        Self {
+            width,
+            height,
+            texels,
+        }
+    }
+}
+
+/// An RGBA texture with optional mip chain and per-texture sampler state.
+#[derive(Debug, Clone)]
+pub struct Texture {
+    levels: Vec<MipLevel>,
+    pub filter: Filter,
+    pub mipmap_mode: MipmapMode,
+    pub wrap: WrapMode,
+}
+
+impl Texture {
+    /// Creates a texture from texels stored bottom row first.
+    pub fn from_texels(width: u32, height: u32, texels: Vec<Vec4>) -> Result<Self> {
+        if width == 0 || height == 0 {
+            bail!("Texture dimensions must be non-zero");
+        }
+        if Some(texels.len()) != (width as usize).checked_mul(height as usize) {
+            bail!(
+                "Texture holds {} texels, expected {}x{}",
+                texels.len(),
+                width,
+                height
+            );
+        }
+
+        Ok(Self {
+            levels: vec![MipLevel {
+                width,
+                height,
+                texels,
+            }],
+            filter: Filter::default(),
+            mipmap_mode: MipmapMode::default(),
+            wrap: WrapMode::default(),
+        })
+    }
+
+    /// Loads a PNG, PPM or TGA image and builds its mip chain.
+    pub fn load(path: &Path) -> Result<Self> {
+        let image = image_io::read_image(path)?;
+        let texels = image.pixels.into_iter().map(Vec4::from_argb).collect();
+        let mut texture = Self::from_texels(image.width, image.height, texels)?;
+        texture.generate_mipmaps();
+        Ok(texture)
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_mipmap_mode(mut self, mipmap_mode: MipmapMode) -> Self {
+        self.mipmap_mode = mipmap_mode;
+        self
+    }
+
+    pub fn with_wrap(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn width(&self) -> u32 {
+        self.levels[0].width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.levels[0].height
+    }
+
+    pub fn mip_levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Rebuilds the mip chain from the base level down to 1x1.
+    pub fn generate_mipmaps(&mut self) {
+        self.levels.truncate(1);
+        loop {
+            let last = &self.levels[self.levels.len() - 1];
+            if last.width == 1 && last.height == 1 {
+                break;
+            }
+            let next = last.downsample();
+            self.levels.push(next);
+        }
+    }
+
+    /// Samples the base level at `uv`.
+    pub fn sample(&self, uv: Vec2) -> Vec4 {
+        self.levels[0].sample(uv, self.filter, self.wrap)
+    }
+
+    /// Samples at an explicit level of detail, 0 being the base level.
+    pub fn sample_level(&self, uv: Vec2, lod: f32) -> Vec4 {
+        let max_level = (self.levels.len() - 1) as f32;
+        // NaN would pass through `clamp` and index past the last level
+        let lod = if lod.is_finite() { lod } else { 0.0 };
+        let lod = lod.clamp(0.0, max_level);
+
+        match self.mipmap_mode {
+            MipmapMode::None => self.sample(uv),
+            MipmapMode::Nearest => {
+                self.levels[lod.round() as usize].sample(uv, self.filter, self.wrap)
+            }
+            MipmapMode::Linear => {
+                let lower = lod.floor();
+                let color = self.levels[lower as usize].sample(uv, self.filter, self.wrap);
+                if lower == lod {
+                    return color;
+                }
+                let upper = self.levels[lower as usize + 1].sample(uv, self.filter, self.wrap);
+                color.lerp(upper, lod - lower)
+            }
+        }
+    }
+
+    /// Samples with the level of detail chosen from the screen-space
+    /// derivatives of `uv`, e.g. `Fragment::ddx` and `Fragment::ddy`.
+    pub fn sample_grad(&self, uv: Vec2, uv_dx: Vec2, uv_dy: Vec2) -> Vec4 {
+        if self.levels.len() == 1 || self.mipmap_mode == MipmapMode::None {
+            return self.sample(uv);
+        }
+
+        let size = Vec2::new(self.width() as f32, self.height() as f32);
+        let footprint = (uv_dx * size)
+            .length_squared()
+            .max((uv_dy * size).length_squared());
+        // log2(sqrt(x)) == 0.5 * log2(x)
+        let lod = 0.5 * footprint.max(f32::MIN_POSITIVE).log2();
+        self.sample_level(uv, lod)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkerboard() -> Texture {
+        let texels = (0..16)
+            .map(|i| Vec4::splat(((i / 4 + i % 4) % 2) as f32))
+            .collect();
+        Texture::from_texels(4, 4, texels).unwrap()
+    }
+
+    #[test]
+    fn extreme_coordinates_sample_without_overflow() {
+        let texture = checkerboard();
+        for wrap in [WrapMode::Repeat, WrapMode::Clamp, WrapMode::Mirror] {
+            for filter in [Filter::Nearest, Filter::Bilinear] {
+                let texture = texture.clone().with_wrap(wrap).with_filter(filter);
+                for coordinate in [f32::NAN, f32::INFINITY, -f32::INFINITY, 1e30, -1e30] {
+                    let color = texture.sample(Vec2::new(coordinate, 0.3));
+                    assert!(color.r.is_finite(), "{wrap:?} {filter:?} {coordinate}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn distant_coordinates_wrap_like_nearby_ones() {
+        let texture = checkerboard();
+        for wrap in [WrapMode::Repeat, WrapMode::Mirror] {
+            let texture = texture.clone().with_wrap(wrap);
+            // 2^20 is a whole number of mirrored periods away
+            let near = texture.sample(Vec2::new(0.375, 0.625));
+            let far = texture.sample(Vec2::new(0.375 + 1048576.0, 0.625 - 1048576.0));
+            assert_eq!(near, far, "{wrap:?}");
+        }
+
+        let clamped = texture.with_wrap(WrapMode::Clamp);
+        assert_eq!(
+            clamped.sample(Vec2::new(-1e30, 1e30)),
+            clamped.sample(Vec2::new(-5.0, 5.0))
+        );
+    }
+}
@@ -1,28 +1,214 @@
+use crate::frame_stats::FrameStats;
 use std::time::{Duration, Instant};
 
 // Configuration constants
-pub const ENABLE_FPS_LIMIT: bool = true;
-pub const MAX_FPS: f32 = 320.0;
+pub const DEFAULT_FPS_LIMIT: FpsLimit = FpsLimit::Fixed(320.0);
+pub const DEFAULT_TICK_RATE: f32 = 60.0;
+pub const DEFAULT_MAX_CATCH_UP_STEPS: u32 = 8;
+
+/// The last stretch before a frame deadline is spin-waited, since sleeping
+/// overshoots by a millisecond or more on common schedulers.
+const SPIN_THRESHOLD: Duration = Duration::from_millis(2);
+
+/// Frame rate the pacer holds the loop to.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FpsLimit {
+    /// Run as fast as possible
+    #[default]
+    Unlimited,
+    /// Frames per second
+    Fixed(f32),
+    /// The refresh rate of the window's monitor, when known; unlimited otherwise
+    MatchMonitor,
+}
+
+impl FpsLimit {
+    /// Parses `unlimited`, `monitor` or a frame rate such as `144`. Rates so
+    /// low their frame period doesn't fit a `Duration` are rejected.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "unlimited" => Some(Self::Unlimited),
+            "monitor" => Some(Self::MatchMonitor),
+            _ => match name.parse::<f32>() {
+                Ok(fps)
+                    if fps > 0.0
+                        && fps.is_finite()
+                        && Duration::try_from_secs_f32(1.0 / fps).is_ok() =>
+                {
+                    Some(Self::Fixed(fps))
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Length of one simulation step at `tick_rate` steps per second, or `None`
+/// unless the rate is positive and the step between a nanosecond and the
+/// largest `Duration`.
+pub fn tick_period(tick_rate: f32) -> Option<Duration> {
+    if !(tick_rate > 0.0 && tick_rate.is_finite()) {
+        return None;
+    }
+    Duration::try_from_secs_f32(1.0 / tick_rate)
+        .ok()
+        .filter(|period| !period.is_zero())
+}
 
 pub struct FrameTiming {
-    last_frame_time: Instant,
+    /// Start of the previous frame, `None` until the first one
+    last_frame_time: Option<Instant>,
     frame_count_since_last_update: u32,
     fps_update_time: Instant,
+    fps_limit: FpsLimit,
+    monitor_refresh_rate: Option<f32>,
+    /// When the next frame may start; deadlines advance by whole frame
+    /// periods so sleep overshoot does not accumulate
+    next_deadline: Option<Instant>,
+    stats: FrameStats,
+    /// Wall time between the two most recent frames
+    frame_delta: Duration,
+
+    // Fixed-timestep simulation
+    tick_duration: Duration,
+    max_catch_up_steps: u32,
+    /// Simulation time owed but not yet stepped, always below one tick
+    /// after `advance_simulation`
+    accumulator: Duration,
+    last_advance_time: Option<Instant>,
 }
 
 impl FrameTiming {
     pub fn new() -> Self {
         let now = Instant::now();
         Self {
-            last_frame_time: now,
+            last_frame_time: None,
             frame_count_since_last_update: 0,
             fps_update_time: now,
+            fps_limit: DEFAULT_FPS_LIMIT,
+            monitor_refresh_rate: None,
+            next_deadline: None,
+            stats: FrameStats::default(),
+            frame_delta: Duration::ZERO,
+            tick_duration: Duration::from_secs_f32(1.0 / DEFAULT_TICK_RATE),
+            max_catch_up_steps: DEFAULT_MAX_CATCH_UP_STEPS,
+            accumulator: Duration::ZERO,
+            last_advance_time: None,
+        }
+    }
+
+    /// Wall time between the two most recent `update_fps` calls, for scaling
+    /// per-frame motion. Zero before the second frame.
+    pub fn frame_delta(&self) -> Duration {
+        self.frame_delta
+    }
+
+    /// Fixed simulation steps per second.
+    pub fn tick_rate(&self) -> f32 {
+        1.0 / self.tick_duration.as_secs_f32()
+    }
+
+    /// Panics unless `tick_period` accepts `tick_rate`.
+    pub fn set_tick_rate(&mut self, tick_rate: f32) {
+        let Some(tick_duration) = tick_period(tick_rate) else {
+            panic!("invalid tick rate {tick_rate}");
+        };
+        self.tick_duration = tick_duration;
+        self.accumulator = self.accumulator.min(self.tick_duration);
+    }
+
+    /// Length of one fixed simulation step, the `dt` passed to updates.
+    pub fn tick_duration(&self) -> Duration {
+        self.tick_duration
+    }
+
+    pub fn max_catch_up_steps(&self) -> u32 {
+        self.max_catch_up_steps
+    }
+
+    /// Limits how many steps `advance_simulation` returns at once. After a
+    /// stall the remaining backlog is dropped rather than simulated, so a slow
+    /// machine can't fall into a spiral of ever longer catch-up frames.
+    pub fn set_max_catch_up_steps(&mut self, steps: u32) {
+        self.max_catch_up_steps = steps.max(1);
+    }
+
+    /// Adds the wall time since the previous call to the accumulator and
+    /// returns how many fixed steps the simulation should take this frame.
+    pub fn advance_simulation(&mut self) -> u32 {
+        let now = Instant::now();
+        if let Some(last_advance_time) = self.last_advance_time {
+            self.accumulator += now.duration_since(last_advance_time);
+        }
+        self.last_advance_time = Some(now);
+
+        let due = self.accumulator.as_nanos() / self.tick_duration.as_nanos();
+        let steps = due.min(self.max_catch_up_steps as u128) as u32;
+        self.accumulator = if due > steps as u128 {
+            // Keep only the fractional tick so interpolation stays smooth
+            Duration::from_nanos(
+                (self.accumulator.as_nanos() % self.tick_duration.as_nanos()) as u64,
+            )
+        } else {
+            self.accumulator - self.tick_duration * steps
+        };
+        steps
+    }
+
+    /// How far rendering is between the last two simulation states, in
+    /// `[0, 1)`; blend `previous.lerp(current, alpha)` when drawing.
+    pub fn interpolation_alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.tick_duration.as_secs_f32()
+    }
+
+    pub fn fps_limit(&self) -> FpsLimit {
+        self.fps_limit
+    }
+
+    pub fn set_fps_limit(&mut self, fps_limit: FpsLimit) {
+        self.fps_limit = fps_limit;
+        self.next_deadline = None;
+    }
+
+    /// Sets the refresh rate `FpsLimit::MatchMonitor` paces to, in Hz.
+    pub fn set_monitor_refresh_rate(&mut self, refresh_rate: Option<f32>) {
+        if self.monitor_refresh_rate != refresh_rate {
+            self.monitor_refresh_rate = refresh_rate;
+            self.next_deadline = None;
         }
     }
 
+    /// Time between frames under the current limit, or `None` if unlimited.
+    /// A rate whose period doesn't fit a `Duration` counts as unlimited.
+    pub fn frame_period(&self) -> Option<Duration> {
+        let fps = match self.fps_limit {
+            FpsLimit::Unlimited => return None,
+            FpsLimit::Fixed(fps) => fps,
+            FpsLimit::MatchMonitor => self.monitor_refresh_rate?,
+        };
+        if fps > 0.0 {
+            Duration::try_from_secs_f32(1.0 / fps).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Durations of recent frames, measured between `update_fps` calls.
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    pub fn stats_mut(&mut self) -> &mut FrameStats {
+        &mut self.stats
+    }
+
     pub fn update_fps(&mut self) -> Option<f32> {
         let now = Instant::now();
         self.frame_count_since_last_update += 1;
+        if let Some(last_frame_time) = self.last_frame_time {
+            self.frame_delta = now.duration_since(last_frame_time);
+            self.stats.record(self.frame_delta);
+        }
 
         // Update FPS every second
         if now.duration_since(self.fps_update_time) >= Duration::from_secs(1) {
@@ -31,25 +217,37 @@ impl FrameTiming {
 
             self.frame_count_since_last_update = 0;
             self.fps_update_time = now;
-            
-            self.last_frame_time = now;
+
+            self.last_frame_time = Some(now);
             Some(fps)
         } else {
-            self.last_frame_time = now;
+            self.last_frame_time = Some(now);
             None
         }
     }
 
-    pub fn apply_fps_limit(&self) {
-        if ENABLE_FPS_LIMIT {
-            let target_frame_duration = Duration::from_secs_f32(1.0 / MAX_FPS);
-            let elapsed = self.last_frame_time.elapsed();
+    /// Blocks until the next frame deadline: sleeps for most of the wait and
+    /// spins for the rest.
+    pub fn apply_fps_limit(&mut self) {
+        let Some(period) = self.frame_period() else {
+            self.next_deadline = None;
+            return;
+        };
 
-            if elapsed < target_frame_duration {
-                let sleep_time = target_frame_duration - elapsed;
-                std::thread::sleep(sleep_time);
-            }
+        let now = Instant::now();
+        let deadline = *self.next_deadline.get_or_insert(now);
+        if let Some(sleep_time) = deadline.checked_duration_since(now + SPIN_THRESHOLD) {
+            std::thread::sleep(sleep_time);
+        }
+        while Instant::now() < deadline {
+            std::hint::spin_loop();
         }
+
+        // A frame that ran over by more than a period restarts the schedule
+        // instead of trying to catch up with a burst of unpaced frames
+        let next = deadline + period;
+        let now = Instant::now();
+        self.next_deadline = Some(if next < now { now + period } else { next });
     }
 }
 
@@ -57,4 +255,4 @@ impl Default for FrameTiming {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
@@ -1,4 +1,9 @@
-#[derive(Debug, Clone, Copy)]
+use crate::vec3::Vec3;
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// Four-component vector used both as an RGBA color and as a homogeneous
+/// `xyzw` vector; `x`, `y`, `z` and `w` alias `r`, `g`, `b` and `a`.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Vec4 {
     pub r: f32,
     pub g: f32,
@@ -7,19 +12,65 @@ pub struct Vec4 {
 }
 
 impl Vec4 {
-    pub fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
         Self { r, g, b, a }
     }
 
-    pub fn default() -> Self {
-        Self {
-            r: 0.0,
-            g: 0.0,
-            b: 0.0,
-            a: 1.0,
+    /// Homogeneous vector from a 3D vector and a `w` component.
+    pub fn from_vec3(v: Vec3, w: f32) -> Self {
+        Self::new(v.x, v.y, v.z, w)
+    }
+
+    pub fn x(self) -> f32 {
+        self.r
+    }
+
+    pub fn y(self) -> f32 {
+        self.g
+    }
+
+    pub fn z(self) -> f32 {
+        self.b
+    }
+
+    pub fn w(self) -> f32 {
+        self.a
+    }
+
+    pub fn xyz(self) -> Vec3 {
+        Vec3::new(self.r, self.g, self.b)
+    }
+
+    pub fn splat(value: f32) -> Self {
+        Self::new(value, value, value, value)
+    }
+
+    pub fn dot(self, other: Self) -> f32 {
+        self.r * other.r + self.g * other.g + self.b * other.b + self.a * other.a
+    }
+
+    pub fn length_squared(self) -> f32 {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    /// Unit-length copy of the vector, or zero if the vector has no length.
+    pub fn normalize(self) -> Self {
+        let length = self.length();
+        if length > 0.0 {
+            self / length
+        } else {
+            Self::splat(0.0)
         }
     }
 
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+
     pub fn black() -> Self {
         Self::new(0.0, 0.0, 0.0, 1.0)
     }
@@ -48,7 +99,16 @@ impl Vec4 {
         Self::new(1.0, 0.0, 0.0, 1.0)
     }
 
-    pub fn to_argb(&self) -> u32 {
+    pub fn from_argb(argb: u32) -> Self {
+        Self::new(
+            ((argb >> 16) & 0xFF) as f32 / 255.0,
+            ((argb >> 8) & 0xFF) as f32 / 255.0,
+            (argb & 0xFF) as f32 / 255.0,
+            ((argb >> 24) & 0xFF) as f32 / 255.0,
+        )
+    }
+
+    pub fn to_argb(self) -> u32 {
         let a = (self.a.clamp(0.0, 1.0) * 255.0) as u32;
         let r = (self.r.clamp(0.0, 1.0) * 255.0) as u32;
         let g = (self.g.clamp(0.0, 1.0) * 255.0) as u32;
@@ -58,6 +118,117 @@ impl Vec4 {
     }
 }
 
+impl Add for Vec4 {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(
+            self.r + other.r,
+            self.g + other.g,
+            self.b + other.b,
+            self.a + other.a,
+        )
+    }
+}
+
+impl Sub for Vec4 {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(
+            self.r - other.r,
+            self.g - other.g,
+            self.b - other.b,
+            self.a - other.a,
+        )
+    }
+}
+
+/// Component-wise product, e.g. modulating one color by another.
+impl Mul for Vec4 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.r * other.r,
+            self.g * other.g,
+            self.b * other.b,
+            self.a * other.a,
+        )
+    }
+}
+
+impl Mul<f32> for Vec4 {
+    type Output = Self;
+
+    fn mul(self, scalar: f32) -> Self {
+        Self::new(
+            self.r * scalar,
+            self.g * scalar,
+            self.b * scalar,
+            self.a * scalar,
+        )
+    }
+}
+
+impl Mul<Vec4> for f32 {
+    type Output = Vec4;
+
+    fn mul(self, vector: Vec4) -> Vec4 {
+        vector * self
+    }
+}
+
+impl Div<f32> for Vec4 {
+    type Output = Self;
+
+    fn div(self, scalar: f32) -> Self {
+        Self::new(
+            self.r / scalar,
+            self.g / scalar,
+            self.b / scalar,
+            self.a / scalar,
+        )
+    }
+}
+
+impl Neg for Vec4 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.r, -self.g, -self.b, -self.a)
+    }
+}
+
+impl AddAssign for Vec4 {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Vec4 {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl MulAssign<f32> for Vec4 {
+    fn mul_assign(&mut self, scalar: f32) {
+        *self = *self * scalar;
+    }
+}
+
+impl Default for Vec4 {
+    fn default() -> Self {
+        Self {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+            a: 1.0,
+        }
+    }
+}
+
 impl std::fmt::Display for Vec4 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
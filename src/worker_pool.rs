@@ -0,0 +1,166 @@
+//! Persistent worker threads for `SoftwareCanvas::draw_mesh`, so draws don't
+//! pay for spawning and joining threads every time.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+
+/// Job shared with the workers, its lifetime erased; see `WorkerPool::run`.
+type Job = &'static (dyn Fn() + Sync);
+
+struct State {
+    job: Option<Job>,
+    /// Incremented for every job, so each worker runs each job once
+    generation: u64,
+    /// Workers that haven't finished the current job yet
+    running: usize,
+    /// Whether the current job panicked on a worker
+    panicked: bool,
+    shutdown: bool,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    job_ready: Condvar,
+    job_done: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("worker pool poisoned")
+    }
+}
+
+/// A fixed number of threads that run one job at a time together with the
+/// thread that submits it. Workers are spawned on the first job.
+pub(crate) struct WorkerPool {
+    threads: usize,
+    workers: Vec<JoinHandle<()>>,
+    shared: Arc<Shared>,
+}
+
+impl WorkerPool {
+    /// Pool running jobs on `threads` threads in total, including the caller
+    /// of `run`; 1 runs jobs on the caller alone.
+    pub(crate) fn new(threads: usize) -> Self {
+        Self {
+            threads: threads.max(1),
+            workers: Vec::new(),
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    job: None,
+                    generation: 0,
+                    running: 0,
+                    panicked: false,
+                    shutdown: false,
+                }),
+                job_ready: Condvar::new(),
+                job_done: Condvar::new(),
+            }),
+        }
+    }
+
+    pub(crate) fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Runs `job` on every thread of the pool at once and returns when all
+    /// of them have finished. Jobs split their work by pulling from a shared
+    /// queue. Panics if `job` panicked on any thread.
+    pub(crate) fn run(&mut self, job: &(dyn Fn() + Sync)) {
+        if self.threads == 1 {
+            job();
+            return;
+        }
+        if self.workers.is_empty() {
+            self.spawn_workers();
+        }
+
+        // SAFETY: workers only use the job between here and `running`
+        // dropping to zero, which `WaitForWorkers` waits for before this
+        // function returns or unwinds, so `job` outlives every use
+        let job: Job = unsafe { std::mem::transmute::<&(dyn Fn() + Sync), Job>(job) };
+        {
+            let mut state = self.shared.lock();
+            state.job = Some(job);
+            state.generation += 1;
+            state.running = self.workers.len();
+            state.panicked = false;
+        }
+        self.shared.job_ready.notify_all();
+
+        let wait = WaitForWorkers(&self.shared);
+        job();
+        drop(wait);
+
+        if self.shared.lock().panicked {
+            panic!("render worker panicked");
+        }
+    }
+
+    fn spawn_workers(&mut self) {
+        self.workers = (1..self.threads)
+            .map(|index| {
+                let shared = Arc::clone(&self.shared);
+                std::thread::Builder::new()
+                    .name(format!("render-worker-{index}"))
+                    .spawn(move || worker_loop(&shared))
+                    .expect("failed to spawn render worker")
+            })
+            .collect();
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        self.shared.lock().shutdown = true;
+        self.shared.job_ready.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Blocks until every worker has finished the current job, also while the
+/// submitting thread unwinds from a panic in its own share.
+struct WaitForWorkers<'a>(&'a Shared);
+
+impl Drop for WaitForWorkers<'_> {
+    fn drop(&mut self) {
+        let mut state = self.0.lock();
+        while state.running > 0 {
+            state = self.0.job_done.wait(state).expect("worker pool poisoned");
+        }
+        state.job = None;
+    }
+}
+
+fn worker_loop(shared: &Shared) {
+    let mut generation = 0;
+    loop {
+        let job = {
+            let mut state = shared.lock();
+            loop {
+                if state.shutdown {
+                    return;
+                }
+                if state.generation != generation
+                    && let Some(job) = state.job
+                {
+                    generation = state.generation;
+                    break job;
+                }
+                state = shared.job_ready.wait(state).expect("worker pool poisoned");
+            }
+        };
+
+        let result = panic::catch_unwind(AssertUnwindSafe(job));
+
+        let mut state = shared.lock();
+        state.panicked |= result.is_err();
+        state.running -= 1;
+        if state.running == 0 {
+            shared.job_done.notify_all();
+        }
+    }
+}
@@ -0,0 +1,436 @@
+//! Minimal zlib support for the PNG codec: stored-block compression and a
+//! complete inflate implementation (stored, fixed and dynamic Huffman blocks).
+
+use anyhow::{Result, bail};
+
+/// Wraps `data` in a zlib stream made of uncompressed deflate blocks.
+/// Frames are small enough that skipping compression keeps the encoder trivial.
+pub(crate) fn compress_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = u16::MAX as usize;
+
+    let mut out = Vec::with_capacity(data.len() + data.len() / MAX_BLOCK * 5 + 11);
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(is_final as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// Decompresses a complete zlib stream, verifying its Adler-32 checksum.
+/// Fails once the output would exceed `max_len` bytes, so a small stream
+/// can't expand into gigabytes.
+pub(crate) fn decompress(data: &[u8], max_len: usize) -> Result<Vec<u8>> {
+    if data.len() < 6 {
+        bail!("zlib stream too short");
+    }
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0F != 8 || !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+        bail!("invalid zlib header");
+    }
+    if flg & 0x20 != 0 {
+        bail!("zlib preset dictionaries are not supported");
+    }
+
+    let mut reader = BitReader::new(&data[2..]);
+    let mut out = Vec::new();
+    inflate(&mut reader, &mut out, max_len)?;
+
+    let checksum_start = 2 + reader.byte_position();
+    let Some(checksum) = data.get(checksum_start..checksum_start + 4) else {
+        bail!("zlib stream is missing its checksum");
+    };
+    if u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]) != adler32(&out) {
+        bail!("zlib checksum mismatch");
+    }
+    Ok(out)
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the largest run that cannot overflow before reducing
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bit_buffer: u32,
+    bit_count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            bit_buffer: 0,
+            bit_count: 0,
+        }
+    }
+
+    /// Reads `count` (at most 16) bits, least significant first.
+    fn bits(&mut self, count: u32) -> Result<u32> {
+        while self.bit_count < count {
+            let Some(&byte) = self.data.get(self.position) else {
+                bail!("unexpected end of deflate stream");
+            };
+            self.bit_buffer |= (byte as u32) << self.bit_count;
+            self.position += 1;
+            self.bit_count += 8;
+        }
+        let value = self.bit_buffer & ((1 << count) - 1);
+        self.bit_buffer >>= count;
+        self.bit_count -= count;
+        Ok(value)
+    }
+
+    /// Discards bits up to the next byte boundary.
+    fn align_to_byte(&mut self) {
+        self.bit_buffer = 0;
+        self.bit_count = 0;
+    }
+
+    /// Index of the first byte not yet (even partially) consumed.
+    fn byte_position(&self) -> usize {
+        self.position - (self.bit_count / 8) as usize
+    }
+}
+
+const MAX_CODE_BITS: usize = 15;
+
+/// Canonical Huffman decoding table: the number of codes of each length and
+/// the symbols ordered by code.
+struct Huffman {
+    counts: [u16; MAX_CODE_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self> {
+        let mut counts = [0u16; MAX_CODE_BITS + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+
+        // Reject over-subscribed code sets
+        let mut remaining = 1i32;
+        for &count in &counts[1..] {
+            remaining = (remaining << 1) - count as i32;
+            if remaining < 0 {
+                bail!("invalid Huffman code lengths");
+            }
+        }
+
+        let mut offsets = [0u16; MAX_CODE_BITS + 1];
+        for length in 1..MAX_CODE_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+
+        Ok(Self { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= reader.bits(1)? as i32;
+            let count = count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        bail!("invalid Huffman code")
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order in which code length code lengths are stored in a dynamic block header.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn inflate(reader: &mut BitReader, out: &mut Vec<u8>, max_len: usize) -> Result<()> {
+    loop {
+        let is_final = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => inflate_stored(reader, out, max_len)?,
+            1 => {
+                let (literals, distances) = fixed_tables()?;
+                inflate_block(reader, out, max_len, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_tables(reader)?;
+                inflate_block(reader, out, max_len, &literals, &distances)?;
+            }
+            _ => bail!("invalid deflate block type"),
+        }
+
+        if is_final {
+            return Ok(());
+        }
+    }
+}
+
+fn inflate_stored(reader: &mut BitReader, out: &mut Vec<u8>, max_len: usize) -> Result<()> {
+    reader.align_to_byte();
+    let start = reader.position;
+    let Some(header) = reader.data.get(start..start + 4) else {
+        bail!("unexpected end of deflate stream");
+    };
+    let len = u16::from_le_bytes([header[0], header[1]]);
+    let complement = u16::from_le_bytes([header[2], header[3]]);
+    if len != !complement {
+        bail!("corrupt stored deflate block");
+    }
+
+    let data_start = start + 4;
+    let Some(data) = reader.data.get(data_start..data_start + len as usize) else {
+        bail!("unexpected end of deflate stream");
+    };
+    if out.len() + data.len() > max_len {
+        bail!("deflate output exceeds {} bytes", max_len);
+    }
+    out.extend_from_slice(data);
+    reader.position = data_start + len as usize;
+    Ok(())
+}
+
+fn fixed_tables() -> Result<(Huffman, Huffman)> {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+fn dynamic_tables(reader: &mut BitReader) -> Result<(Huffman, Huffman)> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_length_count = reader.bits(4)? as usize + 4;
+    if literal_count > 286 || distance_count > 30 {
+        bail!("too many deflate codes");
+    }
+
+    let mut code_lengths = [0u8; 19];
+    for &index in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[index] = reader.bits(3)? as u8;
+    }
+    let code_length_table = Huffman::new(&code_lengths)?;
+
+    let mut lengths = vec![0u8; literal_count + distance_count];
+    let mut index = 0;
+    while index < lengths.len() {
+        let symbol = code_length_table.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                if index == 0 {
+                    bail!("deflate length repeat with no previous length");
+                }
+                (lengths[index - 1], 3 + reader.bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.bits(3)? as usize),
+            _ => (0, 11 + reader.bits(7)? as usize),
+        };
+        if index + repeat > lengths.len() {
+            bail!("deflate code lengths overflow");
+        }
+        lengths[index..index + repeat].fill(value);
+        index += repeat;
+    }
+
+    if lengths[256] == 0 {
+        bail!("deflate block has no end-of-block code");
+    }
+    Ok((
+        Huffman::new(&lengths[..literal_count])?,
+        Huffman::new(&lengths[literal_count..])?,
+    ))
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    max_len: usize,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<()> {
+    loop {
+        let symbol = literals.decode(reader)? as usize;
+        match symbol {
+            0..=255 if out.len() == max_len => bail!("deflate output exceeds {} bytes", max_len),
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let code = symbol - 257;
+                let length =
+                    LENGTH_BASE[code] as usize + reader.bits(LENGTH_EXTRA[code] as u32)? as usize;
+
+                let code = distances.decode(reader)? as usize;
+                if code >= DISTANCE_BASE.len() {
+                    bail!("invalid deflate distance code");
+                }
+                let distance = DISTANCE_BASE[code] as usize
+                    + reader.bits(DISTANCE_EXTRA[code] as u32)? as usize;
+                if distance > out.len() {
+                    bail!("deflate distance reaches before the start of the output");
+                }
+                if out.len() + length > max_len {
+                    bail!("deflate output exceeds {} bytes", max_len);
+                }
+
+                // Copies may overlap their own output, so go byte by byte
+                let start = out.len() - distance;
+                for i in 0..length {
+                    out.push(out[start + i]);
+                }
+            }
+            _ => bail!("invalid deflate literal/length code"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Streams written by zlib 1.2 (Python's `zlib.compressobj`)
+    const FIXED_TEXT: &[u8] = b"abracadabra abracadabra abracadabra";
+    const FIXED_STREAM: &str = "78014b4c2a4a4c4e4c4904520a89d8d900ee280d3d";
+    const DYNAMIC_TEXT: &[u8] = b"Edge functions are evaluated exactly on a fixed-point grid, \
+        so triangles sharing an edge never both cover a pixel. Depth is interpolated linearly \
+        in screen space, where z over w is affine, and varyings use perspective-correct weights.";
+    const DYNAMIC_STREAM: &str = "789c1d8f518ec3300844af3207487b8add83507b922059d8022769f7f44b\
+        fac508c17bf05b37623dac4ced161027784a3b64b2826f29b37dd00d8255dfac8fd1d52636d7ba203aa6ab\
+        d8d618885d5c6d831878338d271daf3e77947e47c148427be287239b1a48127df4f67535358aa74c0d519c\
+        cc32a470c1b533affac39772dd8bb2ae39bda4abe214ffa437700431e83198bf9c7c94ee9e111775db673c\
+        ff01386b55b4";
+
+    fn hex(text: &str) -> Vec<u8> {
+        (0..text.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn streams() -> [(&'static [u8], Vec<u8>); 2] {
+        [
+            (FIXED_TEXT, hex(FIXED_STREAM)),
+            (DYNAMIC_TEXT, hex(DYNAMIC_STREAM)),
+        ]
+    }
+
+    #[test]
+    fn inflates_fixed_and_dynamic_huffman_blocks() {
+        let [(_, fixed), (_, dynamic)] = streams();
+        // Block type in bits 1-2 of the first deflate byte
+        assert_eq!((fixed[2] >> 1) & 3, 1);
+        assert_eq!((dynamic[2] >> 1) & 3, 2);
+
+        for (text, stream) in streams() {
+            assert_eq!(decompress(&stream, usize::MAX).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn stored_blocks_round_trip() {
+        let long: Vec<u8> = (0..150_000u32).map(|i| (i * 7 / 3) as u8).collect();
+        for data in [&[][..], b"salmon", &long] {
+            let stream = compress_stored(data);
+            assert_eq!(decompress(&stream, data.len()).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn output_beyond_the_limit_is_rejected() {
+        for (text, stream) in streams() {
+            assert!(decompress(&stream, text.len()).is_ok());
+            assert!(decompress(&stream, text.len() - 1).is_err());
+        }
+        let stored = compress_stored(FIXED_TEXT);
+        assert!(decompress(&stored, FIXED_TEXT.len() - 1).is_err());
+    }
+
+    #[test]
+    fn truncated_streams_are_rejected() {
+        for (_, stream) in streams() {
+            for len in 0..stream.len() {
+                assert!(
+                    decompress(&stream[..len], usize::MAX).is_err(),
+                    "{len} bytes"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn corrupt_streams_are_rejected() {
+        for (text, stream) in streams() {
+            let corrupt = |index: usize, mask: u8| {
+                let mut stream = stream.clone();
+                stream[index] ^= mask;
+                decompress(&stream, usize::MAX)
+            };
+            // Header, reserved block type 3 and checksum
+            assert!(corrupt(0, 0x01).is_err());
+            assert!(corrupt(2, !stream[2] & 0x06).is_err());
+            assert!(corrupt(stream.len() - 1, 0x80).is_err());
+
+            // No single flipped bit may panic or decode to different data;
+            // flips in the padding before the checksum change nothing
+            for index in 0..stream.len() {
+                for bit in 0..8 {
+                    if let Ok(data) = corrupt(index, 1 << bit) {
+                        assert_eq!(data, text, "byte {index} bit {bit}");
+                    }
+                }
+            }
+        }
+    }
+}